@@ -2,19 +2,318 @@ mod env;
 
 use smallauncher_lib::*;
 use std::io;
+use std::io::IsTerminal;
 use std::process;
 
 fn help() -> ! {
-	println!("smallauncher -d   --download <version>");
+	println!("smallauncher -d   --download <version> [download options]");
+	println!("smallauncher -p   --plan     <version> [download options]");
 	println!("smallauncher -c   --check    <version>");
-	println!("smallauncher -r   --run      <version> <username>");
-	println!("smallauncher -s   --search   <version>");
+	println!("smallauncher -r   --run      <version> <username> [launch options]");
+	println!("smallauncher -e   --export   <version> <username> <file> [launch options] [--redact]");
+	println!("smallauncher -s   --search   <version> [download options]");
 	println!("smallauncher -l   --list");
-	println!("smallauncher -la  --list-all");
+	println!("smallauncher -la  --list-all [download options]");
 	println!("smallauncher -a   --authenticate");
+	println!("smallauncher -sd  --server-download <version> [download options]");
+	println!("smallauncher -se  --server-eula     <version>");
+	println!("smallauncher -sp  --server-property <version> [key] [value]");
+	println!("smallauncher -sr  --server-run      <version> [server options]");
+	println!();
+	println!("download options:");
+	println!("  -j  --jobs <count>    files downloaded at the same time");
+	println!("      --retries <count> attempts for each host before giving up");
+	println!("      --fallback <url>  mirror tried when a download fails, 'bmclapi' for bmclapi");
+	println!("      --endpoint <url>  replaces every mojang host, 'bmclapi' for bmclapi");
+	println!("      --manifest-url <url> --meta-url <url> --libraries-url <url> --assets-url <url> --java-url <url>");
+	println!("                        replaces the mojang host of a single kind of resource");
+	println!();
+	println!("launch options:");
+	println!("      --xms <MiB>       initial memory");
+	println!("      --xmx <MiB>       maximum memory");
+	println!("      --jvm-arg <arg>   extra java argument, can be repeated");
+	println!("      --java <path>     java executable used instead of the downloaded one");
+	println!("      --width <pixels>  window width");
+	println!("      --height <pixels> window height");
+	println!("      --fullscreen      starts in fullscreen");
+	println!("      --world <name>    opens a singleplayer world");
+	println!("      --server <host[:port]>");
+	println!("                        joins a server");
+	println!("      --realm <id>      joins a realm");
+	println!("      --wrapper <command>");
+	println!("                        runs the game through a command such as gamemoderun, can be repeated.");
+	println!("                        the command is split on whitespace without any quoting");
+	println!("      --wrapper-arg <arg>");
+	println!("                        adds a single argument, which may contain spaces, to the last wrapper");
+	println!("      --env <name>=<value>");
+	println!("                        sets an environment variable of the game");
+	println!("      --unset-env <name>");
+	println!("                        removes an environment variable of the game");
+	println!("      --pre-launch <command>");
+	println!("                        shell command run before the game starts, the launch is aborted if it fails");
+	println!("      --post-exit <command>");
+	println!("                        shell command run after the game exits, the launcher waits for the game");
+	println!("      --attach          stays attached, prints the game log and a crash summary if the game fails");
+	println!("      --log-level <level>");
+	println!("                        lowest level of the game log printed when attached: trace, debug, info, warn, error or fatal");
+	println!("      --set <name>=<value>");
+	println!("                        sets the value of a ${{name}} in the arguments of the version");
+	println!();
+	println!("export:");
+	println!("  writes the launch as a json document when <file> ends with .json, as a shell script otherwise,");
+	println!("  --redact replaces the access token with ${{auth_access_token}}, files with the token are only readable by their owner");
+	println!();
+	println!("server options:");
+	println!("      --xms <MiB>       initial memory");
+	println!("      --xmx <MiB>       maximum memory");
+	println!("      --gui             opens the server window");
 	process::exit(0);
 }
 
+fn server_options(mut args: std::env::Args) -> server::ServerOptions {
+	let mut options = server::ServerOptions::default();
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"--xms" | "--xmx" => match args.next().map(|memory| memory.parse()) {
+				Some(Ok(memory)) if arg == "--xms" => options.min_memory = Some(memory),
+				Some(Ok(memory)) => options.max_memory = Some(memory),
+				_ => {
+					println!("Invalid memory.");
+					help();
+				}
+			},
+			"--gui" => options.gui = true,
+			_ => {
+				println!("Unknown option '{arg}'.");
+				help();
+			}
+		}
+	}
+	options
+}
+
+/// launch options, the lowest level of the game log printed when attached and, for an `export`, whether the access token is left out
+fn launch_options(mut args: impl Iterator<Item = String>, export: bool) -> (launch::LaunchOptions, gamelog::Level, bool) {
+	let mut options = launch::LaunchOptions::default();
+	let mut log_level = gamelog::Level::Trace;
+	let mut redact = false;
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"--redact" if export => redact = true,
+			"--xms" | "--xmx" => match args.next().map(|memory| memory.parse()) {
+				Some(Ok(memory)) if arg == "--xms" => options.min_memory = Some(memory),
+				Some(Ok(memory)) => options.max_memory = Some(memory),
+				_ => {
+					println!("Invalid memory.");
+					help();
+				}
+			},
+			"--jvm-arg" => match args.next() {
+				Some(jvm_arg) => options.jvm_args.push(jvm_arg),
+				None => {
+					println!("Java argument not specified.");
+					help();
+				}
+			},
+			"--java" => match args.next() {
+				Some(java_path) => options.java_path = Some(java_path.into()),
+				None => {
+					println!("Java path not specified.");
+					help();
+				}
+			},
+			"--width" | "--height" => match args.next().map(|size| size.parse()) {
+				Some(Ok(size)) if arg == "--width" => options.width = Some(size),
+				Some(Ok(size)) => options.height = Some(size),
+				_ => {
+					println!("Invalid window size.");
+					help();
+				}
+			},
+			"--fullscreen" => options.fullscreen = true,
+			"--attach" => options.capture_output = true,
+			"--wrapper" => match args.next() {
+				Some(wrapper) if !wrapper.trim().is_empty() => options.wrappers.push(wrapper.split_whitespace().map(String::from).collect()),
+				_ => {
+					println!("Wrapper command not specified.");
+					help();
+				}
+			},
+			"--wrapper-arg" => match (args.next(), options.wrappers.last_mut()) {
+				(Some(arg), Some(wrapper)) => wrapper.push(arg),
+				(None, _) => {
+					println!("Wrapper argument not specified.");
+					help();
+				}
+				(_, None) => {
+					println!("--wrapper-arg must follow a --wrapper.");
+					help();
+				}
+			},
+			"--env" => match args.next().as_deref().and_then(|variable| variable.split_once('=')) {
+				Some((name, value)) => {
+					options.environment.insert(name.to_string(), Some(value.to_string()));
+				}
+				None => {
+					println!("Environment variable must be <name>=<value>.");
+					help();
+				}
+			},
+			"--pre-launch" | "--post-exit" => match args.next() {
+				Some(command) if arg == "--pre-launch" => options.pre_launch.push(command),
+				Some(command) => options.post_exit.push(command),
+				None => {
+					println!("Hook command not specified.");
+					help();
+				}
+			},
+			"--unset-env" => match args.next() {
+				Some(name) => {
+					options.environment.insert(name, None);
+				}
+				None => {
+					println!("Environment variable not specified.");
+					help();
+				}
+			},
+			"--world" | "--server" | "--realm" => {
+				let Some(destination) = args.next() else {
+					println!("Quick play destination not specified.");
+					help();
+				};
+				options.quick_play = Some(match arg.as_str() {
+					"--world" => launch::QuickPlay::Singleplayer(destination),
+					"--server" => launch::QuickPlay::Multiplayer(destination),
+					_ => launch::QuickPlay::Realms(destination),
+				});
+			}
+			"--log-level" => match args.next().map(|level| level.parse()) {
+				Some(Ok(level)) => {
+					log_level = level;
+					options.capture_output = true;
+				}
+				_ => {
+					println!("Invalid log level.");
+					help();
+				}
+			},
+			"--set" => match args.next().as_deref().and_then(|variable| variable.split_once('=')) {
+				Some((name, value)) => options.variables.set(name, value),
+				None => {
+					println!("Variable must be <name>=<value>.");
+					help();
+				}
+			},
+			_ => {
+				println!("Unknown option '{arg}'.");
+				help();
+			}
+		}
+	}
+	(options, log_level, redact)
+}
+
+/// writes an exported launch, only readable by its owner when it holds the access token
+fn write_export(path: &str, data: &str, executable: bool, private: bool) -> io::Result<()> {
+	let mut options = std::fs::OpenOptions::new();
+	options.write(true).create(true).truncate(true);
+	#[cfg(target_family = "unix")]
+	let mode = match (executable, private) {
+		(true, true) => 0o700,
+		(true, false) => 0o755,
+		(false, true) => 0o600,
+		(false, false) => 0o644,
+	};
+	#[cfg(target_family = "unix")]
+	std::os::unix::fs::OpenOptionsExt::mode(&mut options, mode);
+	#[cfg(target_family = "windows")]
+	let _ = (executable, private);
+	let mut file = options.open(path)?;
+	// the mode is only given to new files
+	#[cfg(target_family = "unix")]
+	file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(mode))?;
+	io::Write::write_all(&mut file, data.as_bytes())
+}
+
+fn print_record(record: &gamelog::LogRecord, log_level: gamelog::Level, color: bool) {
+	if record.level < log_level {
+		return;
+	}
+	let code = match record.level {
+		gamelog::Level::Fatal | gamelog::Level::Error => "31",
+		gamelog::Level::Warn => "33",
+		gamelog::Level::Info => "",
+		gamelog::Level::Debug | gamelog::Level::Trace => "2",
+	};
+	if color && !code.is_empty() {
+		println!("\x1b[{code}m{record}\x1b[0m");
+	} else {
+		println!("{record}");
+	}
+}
+
+fn format_bytes(bytes: u64) -> String {
+	format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0))
+}
+
+fn download_options(mut args: std::env::Args) -> download::DownloadOptions {
+	let mut options = download::DownloadOptions::default();
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"-j" | "--jobs" => match args.next().map(|jobs| jobs.parse()) {
+				Some(Ok(jobs)) => options.concurrency = jobs,
+				_ => {
+					println!("Invalid number of jobs.");
+					help();
+				}
+			},
+			"--retries" => match args.next().map(|retries| retries.parse()) {
+				Some(Ok(retries)) => options.network.retry.attempts = retries,
+				_ => {
+					println!("Invalid number of retries.");
+					help();
+				}
+			},
+			"--fallback" => match args.next().as_deref() {
+				Some("bmclapi") => options.network.mirrors.append(net::Mirrors::bmclapi()),
+				Some(url) => options.network.mirrors.append(net::Mirrors::all(url)),
+				None => {
+					println!("Fallback not specified.");
+					help();
+				}
+			},
+			"--endpoint" => match args.next().as_deref() {
+				Some("bmclapi") => options.network.endpoints = net::Endpoints::bmclapi(),
+				Some(url) => options.network.endpoints = net::Endpoints::all(url),
+				None => {
+					println!("Endpoint not specified.");
+					help();
+				}
+			},
+			"--manifest-url" | "--meta-url" | "--libraries-url" | "--assets-url" | "--java-url" => {
+				let Some(url) = args.next() else {
+					println!("Endpoint not specified.");
+					help();
+				};
+				let endpoints = &mut options.network.endpoints;
+				let endpoint = match arg.as_str() {
+					"--manifest-url" => &mut endpoints.manifest,
+					"--meta-url" => &mut endpoints.meta,
+					"--libraries-url" => &mut endpoints.libraries,
+					"--assets-url" => &mut endpoints.assets,
+					_ => &mut endpoints.java,
+				};
+				*endpoint = Some(url);
+			}
+			_ => {
+				println!("Unknown option '{arg}'.");
+				help();
+			}
+		}
+	}
+	options
+}
+
 fn commands(mut args: std::env::Args) {
 	if args.len() <= 1 {
 		help();
@@ -24,6 +323,7 @@ fn commands(mut args: std::env::Args) {
 	let game_path = path!(&base_path, "minecraft");
 	let jre_path = path!(&base_path, "jre");
 	let auth_path = path!(&base_path, "auth");
+	let server_path = path!(&base_path, "server");
 	let command = match args.next() {
 		Some(cmd) => cmd,
 		None => {
@@ -41,9 +341,41 @@ fn commands(mut args: std::env::Args) {
 			Err(e) => println!("Authentication failed: {:?}", e),
 		},
 		"-d" | "--download" => match args.next() {
-			Some(version) => match download::download_minecraft_version(&game_path, &jre_path, &version) {
-				Ok(_) => println!("Download completed!"),
-				Err(e) => println!("Download failed: {:?}", e),
+			Some(version) => {
+				match download::download_minecraft_version(&game_path, &jre_path, &version, &download_options(args), &download::LogReporter) {
+					Ok(_) => println!("Download completed!"),
+					Err(e) => println!("Download failed: {:?}", e),
+				}
+			}
+			None => {
+				println!("Version not specified.");
+				help();
+			}
+		},
+		"-p" | "--plan" => match args.next() {
+			Some(version) => match download::plan_minecraft_version(&game_path, &jre_path, &version, &download_options(args).network) {
+				Ok(plan) => {
+					for file in &plan.files {
+						let status = if file.valid { "valid  " } else { "missing" };
+						println!(
+							"{status} {:<9} {:>10} {}",
+							format!("{:?}", file.phase),
+							file.task.download_size(),
+							file.task.path.display()
+						);
+					}
+					for (link, _) in plan.links.iter().filter(|(_, valid)| !valid) {
+						println!("missing {:<9} {:>10} {} -> {}", "Link", "", link.path.display(), link.target.display());
+					}
+					println!(
+						"{} of {} files missing, {} of {} to download.",
+						plan.missing().count(),
+						plan.files.len(),
+						format_bytes(plan.missing_bytes()),
+						format_bytes(plan.total_bytes())
+					);
+				}
+				Err(e) => println!("Failed to plan download: {:?}", e),
 			},
 			None => {
 				println!("Version not specified.");
@@ -75,9 +407,48 @@ fn commands(mut args: std::env::Args) {
 						}
 					};
 
-					match launch::launch_minecraft_version(&game_path, &jre_path, &version, &auth) {
-						Ok(_) => println!("Game launched successfully."),
-						Err(e) => println!("Failed to launch game: {:?}", e),
+					let (options, log_level, _) = launch_options(args, false);
+					let mut game = match launch::launch_minecraft_version(&game_path, &jre_path, &version, &auth, &options) {
+						Ok(game) => game,
+						Err(error::Error::PreLaunchHook(command, status)) => {
+							println!("Pre-launch hook '{command}' failed: {status}");
+							return;
+						}
+						Err(e) => {
+							println!("Failed to launch game: {:?}", e);
+							return;
+						}
+					};
+					println!("Game launched successfully.");
+					if !options.capture_output && options.post_exit.is_empty() {
+						return;
+					}
+					if let Some(lines) = game.lines() {
+						let color = io::stdout().is_terminal();
+						let mut stdout = gamelog::LogParser::new();
+						let mut stderr = gamelog::LogParser::new();
+						for line in lines {
+							let parser = match line.stream {
+								game::Stream::Stdout => &mut stdout,
+								game::Stream::Stderr => &mut stderr,
+							};
+							if let Some(record) = parser.push(&line.text) {
+								print_record(&record, log_level, color);
+							}
+						}
+						for record in [stdout.finish(), stderr.finish()].into_iter().flatten() {
+							print_record(&record, log_level, color);
+						}
+					}
+					match game.wait() {
+						Ok(status) if status.success() => println!("Game exited: {status}"),
+						Ok(status) => {
+							println!("Game exited: {status}");
+							if let Some(crash) = game.crash() {
+								print!("{crash}");
+							}
+						}
+						Err(e) => println!("Failed to wait for the game: {:?}", e),
 					}
 				}
 				None => {
@@ -90,6 +461,41 @@ fn commands(mut args: std::env::Args) {
 				help();
 			}
 		},
+		"-e" | "--export" => {
+			let (Some(version), Some(username), Some(file)) = (args.next(), args.next(), args.next()) else {
+				println!("Version, username or file not specified.");
+				help();
+			};
+			let auth = match smallauncher_lib::auth::load(&auth_path, &username) {
+				Ok(Some(auth)) => auth,
+				Ok(None) => smallauncher_lib::auth::auth_offline(&username),
+				Err(e) => {
+					println!("Failed to load authentication: {:?}", e);
+					return;
+				}
+			};
+			let (options, _, redact) = launch_options(args, true);
+			let mut plan = match launch::export_launch(&game_path, &jre_path, &version, &auth, &options) {
+				Ok(plan) => plan,
+				Err(e) => {
+					println!("Failed to plan the launch: {:?}", e);
+					return;
+				}
+			};
+			if redact {
+				plan.redact();
+			}
+			let json = file.ends_with(".json");
+			let data = match json {
+				true => plan.to_json().map_err(error::Error::from),
+				false => Ok(plan.to_shell_script()),
+			};
+			let result = data.and_then(|data| Ok(write_export(&file, &data, !json, !redact)?));
+			match result {
+				Ok(()) => println!("Launch exported to {file}."),
+				Err(e) => println!("Failed to export the launch: {:?}", e),
+			}
+		}
 		"-l" | "--list" => match launch::list_versions(&game_path) {
 			Ok(list) => {
 				if !list.is_empty() {
@@ -105,7 +511,7 @@ fn commands(mut args: std::env::Args) {
 			}
 			Err(e) => println!("Error listing versions: {:?}", e),
 		},
-		"-la" | "--list-all" => match launch::list_all_versions() {
+		"-la" | "--list-all" => match launch::list_all_versions(&download_options(args).network) {
 			Ok(versions) => {
 				if versions.versions.is_empty() {
 					println!("No versions available.");
@@ -118,7 +524,7 @@ fn commands(mut args: std::env::Args) {
 			Err(e) => println!("Failed to list all versions: {:?}", e),
 		},
 		"-s" | "--search" => match args.next() {
-			Some(search) => match launch::list_all_versions() {
+			Some(search) => match launch::list_all_versions(&download_options(args).network) {
 				Ok(versions) => {
 					let mut found = false;
 					for version in versions.versions {
@@ -138,6 +544,78 @@ fn commands(mut args: std::env::Args) {
 				help();
 			}
 		},
+		"-sd" | "--server-download" => match args.next() {
+			Some(version) => {
+				let options = download_options(args);
+				match server::download_server_version(&server_path, &jre_path, &version, &options, &download::LogReporter) {
+					Ok(_) => println!("Server download completed!"),
+					Err(e) => println!("Server download failed: {:?}", e),
+				}
+			}
+			None => {
+				println!("Version not specified.");
+				help();
+			}
+		},
+		"-se" | "--server-eula" => match args.next() {
+			Some(version) => match server::accept_eula(&server::server_directory(&server_path, &version)) {
+				Ok(_) => println!("EULA accepted (https://aka.ms/MinecraftEULA)."),
+				Err(e) => println!("Failed to accept EULA: {:?}", e),
+			},
+			None => {
+				println!("Version not specified.");
+				help();
+			}
+		},
+		"-sp" | "--server-property" => match args.next() {
+			Some(version) => {
+				let directory = server::server_directory(&server_path, &version);
+				let mut properties = match server::ServerProperties::load(&directory) {
+					Ok(properties) => properties,
+					Err(e) => {
+						println!("Failed to read server properties: {:?}", e);
+						return;
+					}
+				};
+				match (args.next(), args.next()) {
+					(None, _) => {
+						for (key, value) in properties.iter() {
+							println!("{key}={value}");
+						}
+					}
+					(Some(key), None) => match properties.get(&key) {
+						Some(value) => println!("{value}"),
+						None => println!("Property '{key}' not set."),
+					},
+					(Some(key), Some(value)) => {
+						properties.set(&key, value);
+						if let Err(e) = properties.save(&directory) {
+							println!("Failed to save server properties: {:?}", e);
+						}
+					}
+				}
+			}
+			None => {
+				println!("Version not specified.");
+				help();
+			}
+		},
+		"-sr" | "--server-run" => match args.next() {
+			Some(version) => match server::launch_server(&server_path, &jre_path, &version, &server_options(args)) {
+				Ok(mut child) => match child.wait() {
+					Ok(status) => println!("Server stopped: {status}"),
+					Err(e) => println!("Failed to wait for the server: {:?}", e),
+				},
+				Err(error::Error::EulaNotAccepted) => {
+					println!("The EULA must be accepted first: smallauncher --server-eula {version}");
+				}
+				Err(e) => println!("Failed to launch server: {:?}", e),
+			},
+			None => {
+				println!("Version not specified.");
+				help();
+			}
+		},
 		_ => help(),
 	}
 }
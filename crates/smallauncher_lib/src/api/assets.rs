@@ -5,6 +5,12 @@ use std::collections;
 #[serde(rename_all = "camelCase")]
 pub struct Assets {
 	pub objects: collections::HashMap<String, Asset>,
+	/// objects are copied to `assets/virtual/<index>/<name>`, used by the `legacy` index
+	#[serde(default, rename = "virtual", skip_serializing_if = "std::ops::Not::not")]
+	pub r#virtual: bool,
+	/// objects are copied to `<game>/resources/<name>`, used by the `pre-1.6` index
+	#[serde(default, rename = "map_to_resources", skip_serializing_if = "std::ops::Not::not")]
+	pub map_to_resources: bool,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
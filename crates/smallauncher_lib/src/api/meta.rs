@@ -1,12 +1,16 @@
 use serde::{Deserialize, Serialize};
+use std::collections;
 use std::fmt;
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct Version {
 	pub id: String,
-	#[serde(default)]
-	pub arguments: Arguments,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub arguments: Option<Arguments>,
+	/// game arguments of versions older than 1.13, separated by spaces
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub minecraft_arguments: Option<String>,
 	pub asset_index: AssetIndex,
 	pub downloads: Downloads,
 	#[serde(default)]
@@ -14,6 +18,8 @@ pub struct Version {
 	pub libraries: Vec<Library>,
 	pub main_class: String,
 	pub r#type: VersionType,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub logging: Option<Logging>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Hash)]
@@ -26,10 +32,41 @@ pub struct JavaVersion {
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct Arguments {
+	#[serde(default)]
 	pub game: Vec<Argument>,
+	#[serde(default)]
 	pub jvm: Vec<Argument>,
 }
 
+impl Version {
+	/// `arguments` of the version, falling back to the legacy `minecraftArguments` and then to the default arguments.
+	/// versions without `arguments.game` get the default jvm arguments followed by their own
+	pub fn arguments(&self) -> Arguments {
+		let default = Arguments::default();
+		let legacy = self.minecraft_arguments.as_ref().map(|arguments| {
+			arguments
+				.split_whitespace()
+				.map(|arg| Argument::String(arg.to_string()))
+				.collect::<Vec<_>>()
+		});
+		let (game, jvm) = match &self.arguments {
+			Some(arguments) => (arguments.game.clone(), arguments.jvm.clone()),
+			None => (Vec::new(), Vec::new()),
+		};
+		if !game.is_empty() {
+			return Arguments {
+				game,
+				jvm: if jvm.is_empty() { default.jvm } else { jvm },
+			};
+		}
+		// the jvm arguments of a loader on top of a legacy version come after the defaults, which hold the classpath and natives
+		Arguments {
+			game: legacy.unwrap_or(default.game),
+			jvm: default.jvm.into_iter().chain(jvm).collect(),
+		}
+	}
+}
+
 impl Default for Arguments {
 	fn default() -> Self {
 		Arguments {
@@ -114,6 +151,31 @@ pub enum ArgumentValue {
 	List(Vec<String>),
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Logging {
+	pub client: Option<LoggingConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct LoggingConfig {
+	/// jvm argument with the path of the file as `${path}`
+	pub argument: String,
+	pub file: LoggingFile,
+	/// `log4j2-xml`
+	pub r#type: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct LoggingFile {
+	pub id: String,
+	pub sha1: String,
+	pub size: usize,
+	pub url: String,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct AssetIndex {
@@ -155,6 +217,30 @@ pub struct Library {
 	pub downloads: Option<LibraryDownload>,
 	pub natives: Option<serde_json::Value>,
 	pub rules: Option<Vec<Rule>>,
+	/// maven repository of libraries without `downloads`, mojang libraries when missing
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub url: Option<String>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub sha1: Option<String>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub size: Option<usize>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub extract: Option<Extract>,
+}
+
+impl Library {
+	#[inline(always)]
+	pub fn coordinate(&self) -> Result<crate::maven::Coordinate, crate::error::Error> {
+		self.name.parse()
+	}
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Extract {
+	/// prefixes of the entries of the natives jar that are not extracted
+	#[serde(default)]
+	pub exclude: Vec<String>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
@@ -178,6 +264,8 @@ pub struct Artifact {
 	pub url: String,
 	pub path: String,
 	pub sha1: String,
+	#[serde(default)]
+	pub size: usize,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
@@ -185,6 +273,8 @@ pub struct Artifact {
 pub struct Rule {
 	pub action: Action,
 	pub os: Option<Os>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub features: Option<collections::HashMap<String, bool>>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
@@ -192,12 +282,17 @@ pub struct Rule {
 pub struct Os {
 	pub name: Option<OsName>,
 	pub arch: Option<Arch>,
+	/// regex matched against the os version
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub version: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub enum Arch {
 	X86,
+	#[serde(other)]
+	Unknown,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
@@ -206,6 +301,8 @@ pub enum OsName {
 	Windows,
 	Osx,
 	Linux,
+	#[serde(other)]
+	Unknown,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
@@ -214,3 +311,20 @@ pub enum Action {
 	Allow,
 	Disallow,
 }
+
+/// release `1.12.2` without libraries nor arguments, with the top level fields of `fields` replacing its own
+#[cfg(test)]
+pub(crate) fn test_version(fields: serde_json::Value) -> Version {
+	let mut version = serde_json::json!({
+		"id": "1.12.2",
+		"assetIndex": { "id": "1.12", "sha1": "", "size": 0, "totalSize": 0, "url": "" },
+		"downloads": { "client": { "url": "", "size": 0, "sha1": "" } },
+		"libraries": [],
+		"mainClass": "net.minecraft.client.main.Main",
+		"type": "release"
+	});
+	if let (Some(version), serde_json::Value::Object(fields)) = (version.as_object_mut(), fields) {
+		version.extend(fields);
+	}
+	serde_json::from_value(version).unwrap()
+}
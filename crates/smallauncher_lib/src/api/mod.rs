@@ -7,8 +7,8 @@ use serde::de::DeserializeOwned;
 
 use crate::*;
 
-pub(crate) fn get_from_url<T: DeserializeOwned>(url: &str) -> Result<T, error::Error> {
-	Ok(ureq::get(url).call()?.into_json()?)
+pub(crate) fn get_from_url<T: DeserializeOwned>(network: &net::Network, resource: net::Resource, url: &str) -> Result<T, error::Error> {
+	net::with_retry(network, resource, url, |url| Ok(ureq::get(url).call()?.into_json()?))
 }
 
 #[cfg(test)]
@@ -17,17 +17,53 @@ mod tests {
 
 	#[test]
 	fn java_version_api() {
-		let _java_versions: java::JavaVersions = get_from_url(java::JavaVersions::DEFAULT_URL).unwrap();
+		let network = net::Network::default();
+		let _java_versions: java::JavaVersions = get_from_url(&network, net::Resource::Java, java::JavaVersions::DEFAULT_URL).unwrap();
 	}
 	#[test]
 	fn manifest_api() {
-		let _manifest: manifest::Manifest = get_from_url(manifest::Manifest::DEFAULT_URL).unwrap();
+		let network = net::Network::default();
+		let _manifest: manifest::Manifest = get_from_url(&network, net::Resource::Manifest, manifest::Manifest::DEFAULT_URL).unwrap();
 	}
 	#[test]
 	fn all_versions_api() {
-		let manifest: manifest::Manifest = get_from_url(manifest::Manifest::DEFAULT_URL).unwrap();
+		let network = net::Network::default();
+		let manifest: manifest::Manifest = get_from_url(&network, net::Resource::Manifest, manifest::Manifest::DEFAULT_URL).unwrap();
 		for version in manifest.versions {
-			let _version: meta::Version = get_from_url(&version.url).unwrap();
+			let _version: meta::Version = get_from_url(&network, net::Resource::Meta, &version.url).unwrap();
 		}
 	}
+	#[test]
+	fn legacy_minecraft_arguments() {
+		let version = meta::test_version(serde_json::json!({
+			"id": "1.7.10",
+			"minecraftArguments": "--username ${auth_player_name} --session ${auth_session}  --tweakClass cpw.mods.fml.common.launcher.FMLTweaker",
+			"mainClass": "net.minecraft.launchwrapper.Launch"
+		}));
+		let arguments = version.arguments();
+		assert_eq!(
+			arguments.game,
+			[
+				"--username",
+				"${auth_player_name}",
+				"--session",
+				"${auth_session}",
+				"--tweakClass",
+				"cpw.mods.fml.common.launcher.FMLTweaker"
+			]
+			.map(|arg| meta::Argument::String(arg.to_string()))
+		);
+		assert_eq!(arguments.jvm, meta::Arguments::default().jvm);
+
+		// a loader profile merged on top of the legacy version
+		let version = meta::test_version(serde_json::json!({
+			"id": "fabric-loader-0.15.0-1.12.2",
+			"minecraftArguments": "--username ${auth_player_name}",
+			"arguments": { "game": [], "jvm": ["-DFabricMcEmu= net.minecraft.client.main.Main "] },
+			"mainClass": "net.fabricmc.loader.impl.launch.knot.KnotClient"
+		}));
+		let mut jvm = meta::Arguments::default().jvm;
+		jvm.push(meta::Argument::String("-DFabricMcEmu= net.minecraft.client.main.Main ".to_string()));
+		assert_eq!(version.arguments().jvm, jvm);
+	}
 }
@@ -0,0 +1,198 @@
+use crate::*;
+
+use std::fmt;
+use std::fs;
+use std::path;
+use std::time;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrashKind {
+	/// report of `crash-reports/` written by the game
+	Game,
+	/// `hs_err_pid*.log` written by the jvm
+	Jvm,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Crash {
+	pub kind: CrashKind,
+	pub path: path::PathBuf,
+	pub description: Option<String>,
+	/// exception of a game crash, problematic frame of a jvm crash
+	pub exception: Option<String>,
+	pub suspected_mods: Vec<String>,
+}
+
+impl fmt::Display for Crash {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self.kind {
+			CrashKind::Game => writeln!(f, "Game crash report: {}", self.path.display())?,
+			CrashKind::Jvm => writeln!(f, "JVM fatal error log: {}", self.path.display())?,
+		}
+		if let Some(description) = &self.description {
+			writeln!(f, "Description: {description}")?;
+		}
+		if let Some(exception) = &self.exception {
+			writeln!(f, "Exception: {exception}")?;
+		}
+		if !self.suspected_mods.is_empty() {
+			writeln!(f, "Suspected mods: {}", self.suspected_mods.join(", "))?;
+		}
+		Ok(())
+	}
+}
+
+/// newest crash report or jvm fatal error log of `game_path` written since `since`
+pub fn find_crash(game_path: &path::Path, since: time::SystemTime) -> Option<Crash> {
+	let reports = fs::read_dir(path!(game_path, "crash-reports"))
+		.into_iter()
+		.flatten()
+		.flatten()
+		.filter(|entry| entry.file_name().to_string_lossy().ends_with(".txt"))
+		.map(|entry| (CrashKind::Game, entry));
+	let jvm = fs::read_dir(game_path)
+		.into_iter()
+		.flatten()
+		.flatten()
+		.filter(|entry| {
+			let name = entry.file_name();
+			let name = name.to_string_lossy();
+			name.starts_with("hs_err_pid") && name.ends_with(".log")
+		})
+		.map(|entry| (CrashKind::Jvm, entry));
+	let (kind, path, _) = reports
+		.chain(jvm)
+		.filter_map(|(kind, entry)| {
+			let modified = entry.metadata().and_then(|metadata| metadata.modified()).ok()?;
+			(modified >= since).then(|| (kind, entry.path(), modified))
+		})
+		.max_by_key(|(_, _, modified)| *modified)?;
+	let data = fs::read(&path).ok()?;
+	let data = String::from_utf8_lossy(&data);
+	Some(match kind {
+		CrashKind::Game => parse_crash_report(&data, path),
+		CrashKind::Jvm => parse_jvm_crash(&data, path),
+	})
+}
+
+pub fn parse_crash_report(data: &str, path: path::PathBuf) -> Crash {
+	let mut crash = Crash {
+		kind: CrashKind::Game,
+		path,
+		description: None,
+		exception: None,
+		suspected_mods: Vec::new(),
+	};
+	let mut lines = data.lines().peekable();
+	while let Some(line) = lines.next() {
+		if crash.description.is_none() {
+			if let Some(description) = line.strip_prefix("Description:") {
+				crash.description = Some(description.trim().to_string());
+				// the exception is the first line after the description
+				crash.exception = lines.by_ref().map(str::trim).find(|line| !line.is_empty()).map(str::to_string);
+				continue;
+			}
+		}
+		let line = line.trim();
+		let Some(mods) = line.strip_prefix("Suspected Mods:").or_else(|| line.strip_prefix("Suspected Mod:")) else {
+			continue;
+		};
+		let mods = mods.trim();
+		if !mods.is_empty() && !mods.eq_ignore_ascii_case("none") && !mods.eq_ignore_ascii_case("unknown") {
+			crash.suspected_mods.push(mods.to_string());
+		}
+		// one mod on each line indented by a single tab, followed by its details
+		while let Some(line) = lines.next_if(|line| line.starts_with('\t')) {
+			if !line.starts_with("\t\t") {
+				crash.suspected_mods.push(line.trim().to_string());
+			}
+		}
+	}
+	crash
+}
+
+pub fn parse_jvm_crash(data: &str, path: path::PathBuf) -> Crash {
+	let comments: Vec<&str> = data
+		.lines()
+		.map_while(|line| line.strip_prefix('#'))
+		.map(str::trim)
+		.filter(|line| !line.is_empty())
+		.collect();
+	let description = comments
+		.iter()
+		.find(|line| !line.starts_with("A fatal error has been detected"))
+		.map(|line| line.to_string());
+	let exception = comments
+		.iter()
+		.position(|line| line.starts_with("Problematic frame:"))
+		.and_then(|i| comments.get(i + 1))
+		.map(|line| line.to_string());
+	Crash {
+		kind: CrashKind::Jvm,
+		path,
+		description,
+		exception,
+		suspected_mods: Vec::new(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parse_reports() {
+		let report = "---- Minecraft Crash Report ----
+// Ouch. That hurt :(
+
+Time: 2024-01-01 12:00:00
+Description: Rendering overlay
+
+java.lang.NullPointerException: Cannot invoke \"Object.toString()\"
+\tat net.minecraft.client.Foo.bar(Foo.java:10)
+
+A detailed walkthrough of the error, its code path and all known details is as follows:
+---------------------------------------------------------------------------------------
+
+-- Head --
+Thread: Render thread
+Suspected Mods:
+\tSodium (sodium), Version: 0.5.3
+\t\tIssue tracker URL: https://github.com/CaffeineMC/sodium-fabric/issues
+\tFabric API (fabric-api), Version: 0.91.0
+Stacktrace:
+";
+		let crash = parse_crash_report(report, path!("crash-2024-01-01_12.00.00-client.txt"));
+		assert_eq!(crash.description.as_deref(), Some("Rendering overlay"));
+		assert_eq!(
+			crash.exception.as_deref(),
+			Some("java.lang.NullPointerException: Cannot invoke \"Object.toString()\"")
+		);
+		assert_eq!(
+			crash.suspected_mods,
+			["Sodium (sodium), Version: 0.5.3", "Fabric API (fabric-api), Version: 0.91.0"]
+		);
+
+		let hs_err = "#
+# A fatal error has been detected by the Java Runtime Environment:
+#
+#  SIGSEGV (0xb) at pc=0x00007f3c2c0b1234, pid=4242, tid=4243
+#
+# JRE version: OpenJDK Runtime Environment (17.0.8+7) (build 17.0.8+7)
+# Problematic frame:
+# C  [liblwjgl.so+0x1234]  Java_org_lwjgl_system_JNI_invokeV+0x10
+#
+
+---------------  S U M M A R Y ------------
+";
+		let crash = parse_jvm_crash(hs_err, path!("hs_err_pid4242.log"));
+		assert_eq!(
+			crash.description.as_deref(),
+			Some("SIGSEGV (0xb) at pc=0x00007f3c2c0b1234, pid=4242, tid=4243")
+		);
+		assert_eq!(
+			crash.exception.as_deref(),
+			Some("C  [liblwjgl.so+0x1234]  Java_org_lwjgl_system_JNI_invokeV+0x10")
+		);
+	}
+}
@@ -1,116 +1,386 @@
 use crate::*;
 
 use log::*;
+use std::cell::Cell;
 use std::io::Write;
-use std::{fs, io, path};
+use std::sync::{atomic, Mutex};
+use std::{collections, fs, io, path, thread};
 
 pub const RESOURCES_URL: &'static str = "https://resources.download.minecraft.net";
+/// repository of the libraries that only have a maven name
+pub const LIBRARIES_URL: &str = "https://libraries.minecraft.net";
+pub const DEFAULT_CONCURRENCY: usize = 8;
+const BUFFER_SIZE: usize = 64 * 1024;
 
-fn download<W: Write>(url: &str, writer: &mut W) -> Result<u64, error::Error> {
-	let mut reader = ureq::get(url).call()?.into_reader();
-	Ok(io::copy(&mut reader, writer)?)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DownloadOptions {
+	/// maximum number of files downloaded at the same time
+	pub concurrency: usize,
+	pub network: net::Network,
 }
 
-pub fn download_minecraft_version(minecraft_path: &path::Path, jre_path: &path::Path, name: &str) -> Result<(), error::Error> {
-	let manifest: api::manifest::Manifest = api::get_from_url(api::manifest::Manifest::DEFAULT_URL)?;
-	let Some(version) = manifest.get_version(name) else {
-		return Err(error::Error::VersionNotFound);
-	};
-	let meta: api::meta::Version = api::get_from_url(&version.url)?;
-	let assets: api::assets::Assets = api::get_from_url(&meta.asset_index.url)?;
-	{
-		let data_meta = serde_json::to_string_pretty(&meta)?;
-		let data_assets = serde_json::to_string_pretty(&assets)?;
+impl Default for DownloadOptions {
+	#[inline(always)]
+	fn default() -> Self {
+		Self {
+			concurrency: DEFAULT_CONCURRENCY,
+			network: net::Network::default(),
+		}
+	}
+}
 
-		let path_client = path!(minecraft_path, "versions", &version.id, format!("{0}.jar", version.id));
-		let path_meta = path!(minecraft_path, "versions", &version.id, format!("{0}.json", version.id));
-		let path_assets = path!(minecraft_path, "assets", "indexes", format!("{0}.json", meta.asset_index.id));
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Phase {
+	Client,
+	Server,
+	Java,
+	Libraries,
+	Natives,
+	Assets,
+}
 
-		let mut file_meta = file::create_or_open_file(&path_meta)?;
-		let mut file_assets = file::create_or_open_file(&path_assets)?;
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Task {
+	pub resource: net::Resource,
+	pub url: String,
+	pub path: path::PathBuf,
+	/// empty when the repository does not publish it, the file is then only checked by its size
+	pub sha1: String,
+	pub size: usize,
+	pub executable: bool,
+	/// lzma compressed variant of the file, preferred when present
+	pub lzma: Option<api::java::Download>,
+}
 
-		if !file::file_hash(&meta.downloads.client.sha1, &path_client).unwrap_or_default() {
-			let mut file_client = file::create_or_open_file(&path_client)?;
-			info!("Downloading client: {path_client:?}");
-			download(&meta.downloads.client.url, &mut file_client)?;
+impl Task {
+	/// the file on disk matches the sha1, or the size when the sha1 is unknown
+	pub fn is_valid(&self) -> bool {
+		if self.sha1.is_empty() {
+			return fs::metadata(&self.path).is_ok_and(|metadata| self.size == 0 || metadata.len() == self.size as u64);
 		}
+		file::file_hash(&self.sha1, &self.path).unwrap_or_default()
+	}
+	/// bytes transferred to download this file
+	#[inline(always)]
+	pub fn download_size(&self) -> usize {
+		self.lzma.as_ref().map_or(self.size, |lzma| lzma.size)
+	}
+}
 
-		file_meta.write(data_meta.as_bytes())?;
-		file_assets.write(data_assets.as_bytes())?;
+#[derive(Debug)]
+pub enum Event<'a> {
+	/// sent once before anything is downloaded, with the totals of every phase
+	Start {
+		files: usize,
+		bytes: u64,
+	},
+	PhaseStart {
+		phase: Phase,
+		files: usize,
+		bytes: u64,
+	},
+	PhaseEnd {
+		phase: Phase,
+		failed: usize,
+	},
+	FileStart {
+		task: &'a Task,
+	},
+	/// `bytes` of the task downloaded since its last event, bytes fetched again by a retry are not counted
+	Progress {
+		task: &'a Task,
+		bytes: u64,
+	},
+	/// the file on disk already matches its sha1
+	FileSkipped {
+		task: &'a Task,
+	},
+	FileDone {
+		task: &'a Task,
+	},
+	FileFailed {
+		task: &'a Task,
+		error: &'a error::Error,
+	},
+	End {
+		failed: usize,
+	},
+}
+
+/// receives download events, it is called from every worker thread
+pub trait Reporter: Sync {
+	fn report(&self, event: Event<'_>);
+}
+
+impl<F: Fn(Event<'_>) + Sync> Reporter for F {
+	#[inline(always)]
+	fn report(&self, event: Event<'_>) {
+		self(event)
 	}
-	{
-		//this file not is used per smallauncher to launch the game, but some mod installers (forge) expect this file exists
-		let profile_path = path!(minecraft_path, "launcher_profiles.json");
-		if !profile_path.exists() {
-			fs::write(profile_path, r#"{"profiles":{}}"#)?;
+}
+
+/// writes events to the `log` crate
+pub struct LogReporter;
+
+impl Reporter for LogReporter {
+	fn report(&self, event: Event<'_>) {
+		match event {
+			Event::Start { files, bytes } => info!("Checking {files} files ({bytes} bytes)"),
+			Event::PhaseStart { phase, files, bytes } => info!("Checking {phase:?}: {files} files ({bytes} bytes)"),
+			Event::PhaseEnd { phase, failed: 0 } => debug!("{phase:?} done"),
+			Event::PhaseEnd { phase, failed } => warn!("{phase:?} done, {failed} files failed"),
+			Event::FileStart { task } => info!("Downloading {:?}", task.path),
+			Event::FileFailed { task, error } => error!("Failed to download {:?}: {error:?}", task.path),
+			Event::Progress { .. } | Event::FileSkipped { .. } | Event::FileDone { .. } | Event::End { .. } => {}
 		}
 	}
-	{
-		let java_versions: api::java::JavaVersions = api::get_from_url(api::java::JavaVersions::DEFAULT_URL)?;
-		let java_version = meta.java_version;
-		#[cfg(target_os = "linux")]
-		let version = java_versions.linux;
-		#[cfg(target_os = "windows")]
-		let version = java_versions.windows_x64;
-		#[cfg(target_os = "macos")]
-		let version = java_versions.mac_os;
-		let java_component = java_version.component;
-		let list = match java_component.as_str() {
-			"java-runtime-alpha" => version.java_runtime_alpha,
-			"java-runtime-beta" => version.java_runtime_beta,
-			"java-runtime-delta" => version.java_runtime_delta,
-			"java-runtime-gamma" => version.java_runtime_gamma,
-			"java-runtime-gamma-snapshot" => version.java_runtime_gamma_snapshot,
-			"jre-legacy" => version.jre_legacy,
-			_ => return Err(error::Error::JavaVersionNotSupported),
-		};
-		let Some(version) = list.get(0) else {
-			return Err(error::Error::JavaVersionNotSupported);
-		};
-		let files: api::java::Files = api::get_from_url(&version.manifest.url)?;
-		for (path, file) in files.files {
-			let path = path!(jre_path, &java_component, path);
-			match file {
-				api::java::File::Directory => fs::create_dir_all(path)?,
-				api::java::File::Link { .. } => {}
-				#[cfg(target_family = "unix")]
-				api::java::File::File { downloads, executable } => {
-					let file_download = downloads.raw;
-					use std::os::unix::fs::PermissionsExt;
-					if !file::file_hash(&file_download.sha1, &path).unwrap_or_default() {
-						let mut file = file::create_or_open_file(&path)?;
-						info!("Downloading java file: {path:?}");
-						download(&file_download.url, &mut file)?;
-						if executable {
-							let perm = fs::Permissions::from_mode(0o755);
-							file.set_permissions(perm)?;
-						}
-					}
-				}
-				#[cfg(not(target_family = "unix"))]
-				api::java::File::File { downloads, .. } => {
-					let file_download = downloads.raw;
-					if !file::file_hash(&file_download.sha1, &path).unwrap_or_default() {
-						let mut file = file::create_or_open_file(&path)?;
-						info!("Downloading java file: {path:?}");
-						download(&file_download.url, &mut file)?;
+}
+
+/// tells `progress` the position reached in the file after each read
+struct ProgressReader<'a, R> {
+	inner: R,
+	position: u64,
+	progress: &'a dyn Fn(u64),
+}
+
+impl<R: io::Read> io::Read for ProgressReader<'_, R> {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		let read = self.inner.read(buf)?;
+		self.position += read as u64;
+		(self.progress)(self.position);
+		Ok(read)
+	}
+}
+
+/// turns the positions reached by every attempt at a task into `Event::Progress`,
+/// so bytes fetched again by a retry or a fallback are only reported once
+struct Progress<'a> {
+	task: &'a Task,
+	reporter: &'a dyn Reporter,
+	reported: Cell<u64>,
+}
+
+impl<'a> Progress<'a> {
+	fn new(task: &'a Task, reporter: &'a dyn Reporter) -> Self {
+		Self {
+			task,
+			reporter,
+			reported: Cell::new(0),
+		}
+	}
+
+	fn reach(&self, position: u64) {
+		let position = match self.task.download_size() {
+			0 => position,
+			size => position.min(size as u64),
+		};
+		if position > self.reported.get() {
+			let bytes = position - self.reported.replace(position);
+			self.reporter.report(Event::Progress { task: self.task, bytes });
+		}
+	}
+}
+
+/// moves `part` into `path` if its size and sha1 match, otherwise deletes it
+fn commit_part(part: &path::Path, path: &path::Path, sha1: &str, size: usize) -> Result<(), error::Error> {
+	let length = fs::metadata(part)?.len();
+	if (size != 0 && length != size as u64) || (!sha1.is_empty() && !file::file_hash(sha1, &part)?) {
+		fs::remove_file(part)?;
+		return Err(error::Error::Integrity(path.to_path_buf()));
+	}
+	fs::rename(part, path)?;
+	Ok(())
+}
+
+/// bytes of the `.part` of a file of `size` bytes that can be resumed, a part longer than the file is started over
+fn resume_offset(part: &path::Path, size: usize) -> u64 {
+	let offset = fs::metadata(part).map(|metadata| metadata.len()).unwrap_or_default();
+	if size != 0 && offset > size as u64 {
+		0
+	} else {
+		offset
+	}
+}
+
+/// writes `reader` into `part` after its first `offset` bytes, the part is truncated when `offset` is 0
+fn write_part(part: &path::Path, reader: impl io::Read, offset: u64, progress: &dyn Fn(u64)) -> Result<(), error::Error> {
+	let mut file = file::open_part_file(&part, offset != 0)?;
+	let mut reader = ProgressReader {
+		inner: reader,
+		position: offset,
+		progress,
+	};
+	io::copy(&mut reader, &mut file)?;
+	file.flush()?;
+	Ok(())
+}
+
+/// streams `url` into the `.part` sibling of `path`, continuing a previous partial download with a Range request,
+/// and only moves it into place once its size and sha1 are verified
+fn download(url: &str, path: &path::Path, sha1: &str, size: usize, progress: &dyn Fn(u64)) -> Result<(), error::Error> {
+	let part = file::part_path(&path);
+	let mut offset = resume_offset(&part, size);
+	// without a size a complete part can not be told apart from a partial one, except by its sha1
+	let complete = match size {
+		0 => offset != 0 && !sha1.is_empty() && file::file_hash(sha1, &part)?,
+		size => offset == size as u64,
+	};
+	if !complete {
+		let request = |offset: u64| {
+			let mut request = ureq::get(url);
+			if offset != 0 {
+				debug!("Resuming {path:?} from byte {offset}");
+				request = request.set("Range", &format!("bytes={offset}-"));
+			}
+			request.call()
+		};
+		let response = match request(offset) {
+			Err(ureq::Error::Status(416, _)) if offset != 0 => {
+				debug!("Can not resume {path:?} from byte {offset}, starting over");
+				offset = 0;
+				request(0)?
+			}
+			response => response?,
+		};
+		// a server ignoring the range sends the whole file
+		let offset = if response.status() == 206 { offset } else { 0 };
+		write_part(&part, response.into_reader(), offset, progress)?;
+	}
+	commit_part(&part, path, sha1, size)
+}
+
+/// streams the lzma file at `url` through the decompressor into the `.part` sibling of `path`,
+/// the decompressed file is verified against the raw `sha1` and `size`
+fn download_lzma(url: &str, path: &path::Path, sha1: &str, size: usize, progress: &dyn Fn(u64)) -> Result<(), error::Error> {
+	let part = file::part_path(&path);
+	let response = ureq::get(url).call()?;
+	write_lzma_part(&part, response.into_reader(), progress)?;
+	commit_part(&part, path, sha1, size)
+}
+
+/// decompresses the lzma stream `reader` into `part`, `progress` follows the compressed bytes
+fn write_lzma_part(part: &path::Path, reader: impl io::Read, progress: &dyn Fn(u64)) -> Result<(), error::Error> {
+	let reader = ProgressReader {
+		inner: reader,
+		position: 0,
+		progress,
+	};
+	let mut reader = io::BufReader::with_capacity(BUFFER_SIZE, reader);
+	let mut file = io::BufWriter::new(file::open_part_file(&part, false)?);
+	lzma_rs::lzma_decompress(&mut reader, &mut file)?;
+	file.into_inner().map_err(io::IntoInnerError::into_error)?.flush()?;
+	Ok(())
+}
+
+fn download_task(task: &Task, network: &net::Network, reporter: &dyn Reporter) -> Result<(), error::Error> {
+	if task.is_valid() {
+		reporter.report(Event::FileSkipped { task });
+		return Ok(());
+	}
+	reporter.report(Event::FileStart { task });
+	let progress = Progress::new(task, reporter);
+	let progress = |position| progress.reach(position);
+	let raw = || {
+		net::with_retry(network, task.resource, &task.url, |url| {
+			download(url, &task.path, &task.sha1, task.size, &progress)
+		})
+	};
+	match &task.lzma {
+		Some(lzma) => {
+			let result = net::with_retry(network, task.resource, &lzma.url, |url| {
+				download_lzma(url, &task.path, &task.sha1, task.size, &progress)
+			});
+			if let Err(e) = result {
+				warn!("Failed to download lzma {:?}, falling back to the raw file: {e:?}", task.path);
+				raw()?;
+			}
+		}
+		None => raw()?,
+	}
+	#[cfg(target_family = "unix")]
+	if task.executable {
+		use std::os::unix::fs::PermissionsExt;
+		fs::set_permissions(&task.path, fs::Permissions::from_mode(0o755))?;
+	}
+	reporter.report(Event::FileDone { task });
+	Ok(())
+}
+
+/// downloads every task using up to `options.concurrency` workers, files already matching their sha1 are skipped.
+/// a failed file does not stop the others, all failures are returned together once the batch is done
+pub fn download_tasks(tasks: &[Task], options: &DownloadOptions, reporter: &dyn Reporter) -> Result<(), error::Error> {
+	let next = atomic::AtomicUsize::new(0);
+	let failures = Mutex::new(Vec::new());
+	let workers = options.concurrency.clamp(1, tasks.len().max(1));
+	thread::scope(|scope| {
+		for _ in 0..workers {
+			scope.spawn(|| {
+				while let Some(task) = tasks.get(next.fetch_add(1, atomic::Ordering::Relaxed)) {
+					if let Err(error) = download_task(task, &options.network, reporter) {
+						reporter.report(Event::FileFailed { task, error: &error });
+						failures.lock().unwrap().push((task.path.clone(), error));
 					}
 				}
-			}
+			});
 		}
+	});
+	let failures = failures.into_inner().unwrap();
+	if failures.is_empty() {
+		Ok(())
+	} else {
+		Err(error::Error::Download(failures))
 	}
+}
 
+fn get_java_files(java_component: &str, network: &net::Network) -> Result<collections::HashMap<String, api::java::File>, error::Error> {
+	let java_versions: api::java::JavaVersions = api::get_from_url(network, net::Resource::Java, api::java::JavaVersions::DEFAULT_URL)?;
 	#[cfg(target_os = "linux")]
-	const OS_NAME: Option<api::meta::OsName> = Some(api::meta::OsName::Linux);
+	let version = java_versions.linux;
 	#[cfg(target_os = "windows")]
-	const OS_NAME: Option<api::meta::OsName> = Some(api::meta::OsName::Windows);
-	#[cfg(target_arch = "x86")]
-	const ARCH: Option<api::meta::Arch> = Some(api::meta::Arch::X86);
-	#[cfg(not(target_arch = "x86"))]
-	const ARCH: Option<api::meta::Arch> = None;
+	let version = java_versions.windows_x64;
+	#[cfg(target_os = "macos")]
+	let version = java_versions.mac_os;
+	let list = match java_component {
+		"java-runtime-alpha" => version.java_runtime_alpha,
+		"java-runtime-beta" => version.java_runtime_beta,
+		"java-runtime-delta" => version.java_runtime_delta,
+		"java-runtime-gamma" => version.java_runtime_gamma,
+		"java-runtime-gamma-snapshot" => version.java_runtime_gamma_snapshot,
+		"jre-legacy" => version.jre_legacy,
+		_ => return Err(error::Error::JavaVersionNotSupported),
+	};
+	let Some(version) = list.first() else {
+		return Err(error::Error::JavaVersionNotSupported);
+	};
+	let files: api::java::Files = api::get_from_url(network, net::Resource::Java, &version.manifest.url)?;
+	Ok(files.files)
+}
 
-	'l: for library in &meta.libraries {
+/// returns the library and the native library tasks
+fn get_library_tasks(meta: &api::meta::Version, minecraft_path: &path::Path) -> (Vec<Task>, Vec<Task>) {
+	let mut tasks = Vec::new();
+	let mut natives = Vec::new();
+	for library in &meta.libraries {
+		if library
+			.rules
+			.as_ref()
+			.is_some_and(|rules| !rules::is_allowed(rules, &rules::Features::default()))
+		{
+			continue;
+		}
 		let Some(downloads) = &library.downloads else {
+			match library.coordinate() {
+				Ok(coordinate) => tasks.push(Task {
+					resource: net::Resource::Library,
+					url: coordinate.url(library.url.as_deref().unwrap_or(LIBRARIES_URL)),
+					path: path!(minecraft_path, "libraries", coordinate.path()),
+					sha1: library.sha1.clone().unwrap_or_default(),
+					size: library.size.unwrap_or_default(),
+					executable: false,
+					lzma: None,
+				}),
+				Err(e) => warn!("Skipping library {}: {e:?}", library.name),
+			}
 			continue;
 		};
 
@@ -130,46 +400,648 @@ pub fn download_minecraft_version(minecraft_path: &path::Path, jre_path: &path::
 			else {
 				continue;
 			};
-			let path = path!(minecraft_path, "libraries", &library_download.path);
-			if !file::file_hash(&library_download.sha1, &path).unwrap_or_default() {
-				let mut file = file::create_or_open_file(&path)?;
-				info!("Downloading native library: {}", library_download.path);
-				download(&library_download.url, &mut file)?;
-			}
+			natives.push(Task {
+				resource: net::Resource::Library,
+				url: library_download.url.clone(),
+				path: path!(minecraft_path, "libraries", &library_download.path),
+				sha1: library_download.sha1.clone(),
+				size: library_download.size,
+				executable: false,
+				lzma: None,
+			});
 		};
 
-		if let Some(rules) = &library.rules {
-			for api::meta::Rule { os, action } in rules {
-				match (os, action) {
-					(Some(api::meta::Os { name: OS_NAME, arch: ARCH }), api::meta::Action::Allow) => {}
-					(Some(api::meta::Os { name: OS_NAME, arch: ARCH }), api::meta::Action::Disallow) => continue 'l,
-					(None, api::meta::Action::Allow) => {}
-					(None, api::meta::Action::Disallow) => continue 'l,
-					(_, api::meta::Action::Disallow) => {}
-					(_, api::meta::Action::Allow) => continue 'l,
-				}
+		if let Some(library_download) = &downloads.artifact {
+			tasks.push(Task {
+				resource: net::Resource::Library,
+				url: library_download.url.clone(),
+				path: path!(minecraft_path, "libraries", &library_download.path),
+				sha1: library_download.sha1.clone(),
+				size: library_download.size,
+				executable: false,
+				lzma: None,
+			});
+		};
+	}
+	(tasks, natives)
+}
+
+fn get_asset_tasks(assets: &api::assets::Assets, minecraft_path: &path::Path) -> Vec<Task> {
+	assets
+		.objects
+		.values()
+		.map(|asset| {
+			let prefix_hash = &asset.hash[0..2];
+			let hash = &asset.hash;
+			Task {
+				resource: net::Resource::Asset,
+				url: format!("{RESOURCES_URL}/{prefix_hash}/{hash}"),
+				path: path!(minecraft_path, "assets", "objects", prefix_hash, hash),
+				sha1: asset.hash.clone(),
+				size: asset.size,
+				executable: false,
+				lzma: None,
 			}
+		})
+		.collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+	pub path: path::PathBuf,
+	/// relative to the directory of `path`
+	pub target: path::PathBuf,
+}
+
+impl Link {
+	/// the symlink exists and points to `target`
+	pub fn is_valid(&self) -> bool {
+		fs::read_link(&self.path).is_ok_and(|target| target == self.target)
+	}
+
+	fn create(&self) -> Result<(), error::Error> {
+		if self.is_valid() {
+			return Ok(());
+		}
+		if let Some(parent) = self.path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+		if fs::symlink_metadata(&self.path).is_ok() {
+			fs::remove_file(&self.path)?;
 		}
+		debug!("Linking {:?} -> {:?}", self.path, self.target);
+		#[cfg(target_family = "unix")]
+		std::os::unix::fs::symlink(&self.target, &self.path)?;
+		#[cfg(target_family = "windows")]
+		std::os::windows::fs::symlink_file(&self.target, &self.path)?;
+		Ok(())
+	}
+}
 
-		if let Some(library_download) = &downloads.artifact {
-			let path = path!(minecraft_path, "libraries", &library_download.path);
-			if !file::file_hash(&library_download.sha1, &path).unwrap_or_default() {
-				let mut file = file::create_or_open_file(&path)?;
-				info!("Downloading library: {}", library_download.path);
-				download(&library_download.url, &mut file)?;
+/// `path` of a java runtime entry relative to the runtime root, rejecting paths that leave it or go through one of its `links`
+fn entry_path(path: &str, links: &collections::HashSet<path::PathBuf>) -> Result<path::PathBuf, error::Error> {
+	let mut entry = path::PathBuf::new();
+	for component in path::Path::new(path).components() {
+		let path::Component::Normal(name) = component else {
+			return Err(error::Error::InvalidPath(path.into()));
+		};
+		if links.contains(&entry) {
+			return Err(error::Error::InvalidPath(path.into()));
+		}
+		entry.push(name);
+	}
+	if entry.as_os_str().is_empty() {
+		return Err(error::Error::InvalidPath(path.into()));
+	}
+	Ok(entry)
+}
+
+/// `target` of the link at `path`, both relative to the runtime root, rejecting targets that leave it or go through one of its `links`.
+/// the check is done on the manifest alone, as a link followed half way would move the rest of the target somewhere else
+fn link_target(path: &path::Path, target: &str, links: &collections::HashSet<path::PathBuf>) -> Result<path::PathBuf, error::Error> {
+	let mut resolved = path.parent().map(path::Path::to_path_buf).unwrap_or_default();
+	for component in path::Path::new(target).components() {
+		if links.contains(&resolved) {
+			return Err(error::Error::InvalidLink(path.to_path_buf()));
+		}
+		match component {
+			path::Component::CurDir => {}
+			path::Component::ParentDir if resolved.pop() => {}
+			path::Component::Normal(name) => resolved.push(name),
+			_ => return Err(error::Error::InvalidLink(path.to_path_buf())),
+		}
+	}
+	Ok(path::PathBuf::from(target))
+}
+
+pub(crate) fn get_version_meta(name: &str, network: &net::Network) -> Result<api::meta::Version, error::Error> {
+	let manifest: api::manifest::Manifest = api::get_from_url(network, net::Resource::Manifest, api::manifest::Manifest::DEFAULT_URL)?;
+	let Some(version) = manifest.get_version(name) else {
+		return Err(error::Error::VersionNotFound);
+	};
+	api::get_from_url(network, net::Resource::Meta, &version.url)
+}
+
+pub(crate) struct JavaRuntime {
+	pub(crate) directories: Vec<path::PathBuf>,
+	pub(crate) links: Vec<Link>,
+	pub(crate) tasks: Vec<Task>,
+}
+
+/// lists the files of the mojang java runtime `java_component`, installed in `jre_path/java_component`
+pub(crate) fn get_java_runtime(java_component: &str, jre_path: &path::Path, network: &net::Network) -> Result<JavaRuntime, error::Error> {
+	let java_files = get_java_files(java_component, network)?;
+	let java_root = path!(jre_path, java_component);
+	let mut runtime = JavaRuntime {
+		directories: Vec::new(),
+		links: Vec::new(),
+		tasks: Vec::new(),
+	};
+	let links = java_files
+		.iter()
+		.filter(|(_, file)| matches!(file, api::java::File::Link { .. }))
+		.map(|(path, _)| path::PathBuf::from(path))
+		.collect();
+	for (path, file) in &java_files {
+		let entry = entry_path(path, &links)?;
+		let path = path!(&java_root, &entry);
+		match file {
+			api::java::File::Directory => runtime.directories.push(path),
+			api::java::File::Link { target } => runtime.links.push(Link {
+				target: link_target(&entry, target, &links)?,
+				path,
+			}),
+			api::java::File::File { downloads, executable } => runtime.tasks.push(Task {
+				resource: net::Resource::Java,
+				url: downloads.raw.url.clone(),
+				path,
+				sha1: downloads.raw.sha1.clone(),
+				size: downloads.raw.size,
+				executable: *executable,
+				lzma: downloads.lzma.clone(),
+			}),
+		}
+	}
+	Ok(runtime)
+}
+
+/// runs each phase through `download_tasks`, creating the java runtime directories and links around the java phase
+pub(crate) fn run_phases(
+	phases: Vec<(Phase, Vec<Task>)>,
+	java_directories: &[path::PathBuf],
+	java_links: &[Link],
+	options: &DownloadOptions,
+	reporter: &dyn Reporter,
+) -> Result<(), error::Error> {
+	let total = |tasks: &[Task]| tasks.iter().map(|task| task.download_size() as u64).sum::<u64>();
+	reporter.report(Event::Start {
+		files: phases.iter().map(|(_, tasks)| tasks.len()).sum(),
+		bytes: phases.iter().map(|(_, tasks)| total(tasks)).sum(),
+	});
+	for path in java_directories {
+		fs::create_dir_all(path)?;
+	}
+
+	let mut failures = Vec::new();
+	for (phase, tasks) in phases {
+		reporter.report(Event::PhaseStart {
+			phase,
+			files: tasks.len(),
+			bytes: total(&tasks),
+		});
+		let mut failed = match download_tasks(&tasks, options, reporter) {
+			Ok(()) => 0,
+			Err(error::Error::Download(list)) => {
+				let failed = list.len();
+				failures.extend(list);
+				failed
 			}
+			Err(e) => return Err(e),
 		};
+		if phase == Phase::Java {
+			for link in java_links {
+				if let Err(e) = link.create() {
+					error!("Failed to link {:?}: {e:?}", link.path);
+					failures.push((link.path.clone(), e));
+					failed += 1;
+				}
+			}
+		}
+		reporter.report(Event::PhaseEnd { phase, failed });
+	}
+	reporter.report(Event::End { failed: failures.len() });
+
+	if failures.is_empty() {
+		Ok(())
+	} else {
+		Err(error::Error::Download(failures))
+	}
+}
+
+/// where the log4j configuration `id` of the `logging` block of a version is stored
+#[inline(always)]
+pub fn logging_config_path(assets_path: &path::Path, id: &str) -> path::PathBuf {
+	path!(assets_path, "log_configs", id)
+}
+
+struct Resolved {
+	meta: api::meta::Version,
+	assets: api::assets::Assets,
+	java: JavaRuntime,
+	phases: Vec<(Phase, Vec<Task>)>,
+}
+
+/// fetches the metadata of a version and lists every file it needs, without touching the disk
+fn resolve(minecraft_path: &path::Path, jre_path: &path::Path, name: &str, network: &net::Network) -> Result<Resolved, error::Error> {
+	let meta = get_version_meta(name, network)?;
+	let assets: api::assets::Assets = api::get_from_url(network, net::Resource::Meta, &meta.asset_index.url)?;
+	let mut java = get_java_runtime(&meta.java_version.component, jre_path, network)?;
+	let client_task = Task {
+		resource: net::Resource::Meta,
+		url: meta.downloads.client.url.clone(),
+		path: path!(minecraft_path, "versions", &meta.id, format!("{0}.jar", meta.id)),
+		sha1: meta.downloads.client.sha1.clone(),
+		size: meta.downloads.client.size,
+		executable: false,
+		lzma: None,
+	};
+	let mut client_tasks = vec![client_task];
+	if let Some(logging) = meta.logging.as_ref().and_then(|logging| logging.client.as_ref()) {
+		client_tasks.push(Task {
+			resource: net::Resource::Meta,
+			url: logging.file.url.clone(),
+			path: logging_config_path(&path!(minecraft_path, "assets"), &logging.file.id),
+			sha1: logging.file.sha1.clone(),
+			size: logging.file.size,
+			executable: false,
+			lzma: None,
+		});
+	}
+	let (library_tasks, native_tasks) = get_library_tasks(&meta, minecraft_path);
+	let mut phases = vec![
+		(Phase::Client, client_tasks),
+		(Phase::Java, std::mem::take(&mut java.tasks)),
+		(Phase::Libraries, library_tasks),
+		(Phase::Natives, native_tasks),
+		(Phase::Assets, get_asset_tasks(&assets, minecraft_path)),
+	];
+	for (_, tasks) in &mut phases {
+		// several assets or libraries can point to the same file, it must not be written by two workers at once
+		tasks.sort_unstable_by(|a, b| a.path.cmp(&b.path));
+		tasks.dedup_by(|a, b| a.path == b.path);
+	}
+	Ok(Resolved { meta, assets, java, phases })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedFile {
+	pub phase: Phase,
+	pub task: Task,
+	/// the file on disk already matches its sha1
+	pub valid: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstallPlan {
+	pub version: String,
+	pub files: Vec<PlannedFile>,
+	/// java runtime symlinks and whether they already point to their target
+	pub links: Vec<(Link, bool)>,
+}
+
+impl InstallPlan {
+	pub fn total_bytes(&self) -> u64 {
+		self.files.iter().map(|file| file.task.download_size() as u64).sum()
+	}
+	/// bytes that still have to be downloaded
+	pub fn missing_bytes(&self) -> u64 {
+		self.missing().map(|file| file.task.download_size() as u64).sum()
+	}
+	pub fn missing(&self) -> impl Iterator<Item = &PlannedFile> {
+		self.files.iter().filter(|file| !file.valid)
+	}
+}
+
+/// lists every file `download_minecraft_version` would fetch and whether it is already valid on disk, nothing is downloaded
+pub fn plan_minecraft_version(
+	minecraft_path: &path::Path,
+	jre_path: &path::Path,
+	name: &str,
+	network: &net::Network,
+) -> Result<InstallPlan, error::Error> {
+	let resolved = resolve(minecraft_path, jre_path, name, network)?;
+	let files = resolved
+		.phases
+		.into_iter()
+		.flat_map(|(phase, tasks)| tasks.into_iter().map(move |task| (phase, task)))
+		.map(|(phase, task)| PlannedFile {
+			phase,
+			valid: task.is_valid(),
+			task,
+		})
+		.collect();
+	let links = resolved
+		.java
+		.links
+		.into_iter()
+		.map(|link| {
+			let valid = link.is_valid();
+			(link, valid)
+		})
+		.collect();
+	Ok(InstallPlan {
+		version: resolved.meta.id,
+		files,
+		links,
+	})
+}
+
+pub fn download_minecraft_version(
+	minecraft_path: &path::Path,
+	jre_path: &path::Path,
+	name: &str,
+	options: &DownloadOptions,
+	reporter: &dyn Reporter,
+) -> Result<(), error::Error> {
+	let Resolved { meta, assets, java, phases } = resolve(minecraft_path, jre_path, name, &options.network)?;
+	{
+		let data_meta = serde_json::to_string_pretty(&meta)?;
+		let data_assets = serde_json::to_string_pretty(&assets)?;
+
+		let path_meta = path!(minecraft_path, "versions", &meta.id, format!("{0}.json", meta.id));
+		let path_assets = path!(minecraft_path, "assets", "indexes", format!("{0}.json", meta.asset_index.id));
+		let mut file_meta = file::create_or_open_file(&path_meta)?;
+		let mut file_assets = file::create_or_open_file(&path_assets)?;
+
+		file_meta.write(data_meta.as_bytes())?;
+		file_assets.write(data_assets.as_bytes())?;
+	}
+	{
+		//this file not is used per smallauncher to launch the game, but some mod installers (forge) expect this file exists
+		let profile_path = path!(minecraft_path, "launcher_profiles.json");
+		if !profile_path.exists() {
+			fs::write(profile_path, r#"{"profiles":{}}"#)?;
+		}
 	}
-	for (name, asset) in assets.objects {
-		let prefix_hash = &asset.hash[0..2];
-		let hash = &asset.hash;
-		let path = path!(minecraft_path, "assets", "objects", prefix_hash, hash);
-		let url = format!("{RESOURCES_URL}/{prefix_hash}/{hash}");
-		if !file::file_hash(hash, &path).unwrap_or_default() {
-			info!("Downloading asset: {name}");
-			let mut file = file::create_or_open_file(&path)?;
-			download(&url, &mut file)?;
+	let result = run_phases(phases, &java.directories, &java.links, options, reporter);
+	// the assets that were downloaded are still copied, the download failures are the error that matters
+	match (result, materialize_legacy_assets(&assets, &meta.asset_index.id, minecraft_path)) {
+		(Err(e), Err(copy)) => {
+			error!("Failed to copy the legacy assets: {copy:?}");
+			Err(e)
+		}
+		(Err(e), Ok(())) | (Ok(()), Err(e)) => Err(e),
+		(Ok(()), Ok(())) => Ok(()),
+	}
+}
+
+/// directory where versions using the `virtual` or `map_to_resources` asset indexes expect their assets by name
+pub fn legacy_assets_directory(assets: &api::assets::Assets, index_id: &str, minecraft_path: &path::Path) -> Option<path::PathBuf> {
+	if assets.map_to_resources {
+		Some(path!(minecraft_path, "resources"))
+	} else if assets.r#virtual {
+		Some(path!(minecraft_path, "assets", "virtual", index_id))
+	} else {
+		None
+	}
+}
+
+/// copies the downloaded objects to their logical names for the legacy asset layouts
+pub fn materialize_legacy_assets(assets: &api::assets::Assets, index_id: &str, minecraft_path: &path::Path) -> Result<(), error::Error> {
+	let Some(directory) = legacy_assets_directory(assets, index_id, minecraft_path) else {
+		return Ok(());
+	};
+	for (name, asset) in &assets.objects {
+		let path = path!(&directory, name);
+		if !file::normalize(&path).starts_with(&directory) {
+			warn!("Skipping asset outside of {directory:?}: {name}");
+			continue;
+		}
+		if file::file_hash(&asset.hash, &path).unwrap_or_default() {
+			continue;
 		}
+		let object = path!(minecraft_path, "assets", "objects", &asset.hash[0..2], &asset.hash);
+		if !object.exists() {
+			continue;
+		}
+		debug!("Copying asset {name} to {path:?}");
+		if let Some(parent) = path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+		fs::copy(object, path)?;
 	}
 	Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn resume_part() {
+		let root = file::temp_dir("resume-part");
+		let path = path!(&root, "file.txt");
+		let part = file::part_path(&path);
+		// sha1 of "hello world"
+		let sha1 = "2aae6c35c94fcfb415dbe95f408b9ce91ee846ed";
+		let progress = |_| {};
+
+		fs::write(&part, "hello ").unwrap();
+		assert_eq!(resume_offset(&part, 11), 6);
+		write_part(&part, io::Cursor::new("world"), 6, &progress).unwrap();
+		commit_part(&part, &path, sha1, 11).unwrap();
+		assert_eq!(fs::read_to_string(&path).unwrap(), "hello world");
+		assert!(!part.exists());
+
+		fs::write(&part, "a part longer than the file").unwrap();
+		assert_eq!(resume_offset(&part, 11), 0);
+		write_part(&part, io::Cursor::new("hello there"), 0, &progress).unwrap();
+		assert!(matches!(commit_part(&part, &path, sha1, 11), Err(error::Error::Integrity(_))));
+		assert!(!part.exists());
+		fs::remove_dir_all(root).unwrap();
+	}
+
+	#[test]
+	fn lzma_part() {
+		let root = file::temp_dir("lzma-part");
+		let path = path!(&root, "java");
+		let part = file::part_path(&path);
+		let mut compressed = Vec::new();
+		lzma_rs::lzma_compress(&mut io::Cursor::new("hello world"), &mut compressed).unwrap();
+		let position = atomic::AtomicU64::new(0);
+		let progress = |reached| position.store(reached, atomic::Ordering::Relaxed);
+
+		write_lzma_part(&part, compressed.as_slice(), &progress).unwrap();
+		assert_eq!(position.into_inner(), compressed.len() as u64);
+		commit_part(&part, &path, "2aae6c35c94fcfb415dbe95f408b9ce91ee846ed", 11).unwrap();
+		assert_eq!(fs::read_to_string(&path).unwrap(), "hello world");
+
+		assert!(matches!(write_lzma_part(&part, &b"not lzma"[..], &|_| {}), Err(error::Error::Lzma(_))));
+		fs::remove_dir_all(root).unwrap();
+	}
+
+	#[test]
+	fn pool_collects_every_failure() {
+		let root = file::temp_dir("pool-failures");
+		let task = |name: &str, url: &str| Task {
+			resource: net::Resource::Library,
+			url: url.to_string(),
+			path: path!(&root, name),
+			sha1: String::new(),
+			size: 0,
+			executable: false,
+			lzma: None,
+		};
+		fs::write(path!(&root, "present"), "").unwrap();
+		let tasks = [
+			task("missing-a", "invalid://a"),
+			task("present", "invalid://b"),
+			task("missing-b", "invalid://c"),
+		];
+		let options = DownloadOptions {
+			concurrency: 2,
+			network: net::Network {
+				retry: net::RetryPolicy {
+					attempts: 1,
+					..Default::default()
+				},
+				..Default::default()
+			},
+		};
+		let (skipped, failed) = (atomic::AtomicUsize::new(0), atomic::AtomicUsize::new(0));
+		let reporter = |event: Event<'_>| match event {
+			Event::FileSkipped { .. } => _ = skipped.fetch_add(1, atomic::Ordering::Relaxed),
+			Event::FileFailed { .. } => _ = failed.fetch_add(1, atomic::Ordering::Relaxed),
+			_ => {}
+		};
+		let Err(error::Error::Download(failures)) = download_tasks(&tasks, &options, &reporter) else {
+			panic!("the missing files must fail");
+		};
+		let mut paths: Vec<_> = failures.into_iter().map(|(path, _)| path).collect();
+		paths.sort();
+		assert_eq!(paths, [path!(&root, "missing-a"), path!(&root, "missing-b")]);
+		assert_eq!((skipped.into_inner(), failed.into_inner()), (1, 2));
+		fs::remove_dir_all(root).unwrap();
+	}
+
+	#[test]
+	fn phase_events() {
+		let root = file::temp_dir("phase-events");
+		let path = path!(&root, "client.jar");
+		fs::write(&path, "jar").unwrap();
+		let task = Task {
+			resource: net::Resource::Meta,
+			url: String::new(),
+			path,
+			sha1: String::new(),
+			size: 3,
+			executable: false,
+			lzma: None,
+		};
+		let events = Mutex::new(Vec::new());
+		let reporter = |event: Event<'_>| {
+			let name = match event {
+				Event::Start { .. } => "Start",
+				Event::PhaseStart { .. } => "PhaseStart",
+				Event::PhaseEnd { .. } => "PhaseEnd",
+				Event::FileStart { .. } => "FileStart",
+				Event::Progress { .. } => "Progress",
+				Event::FileSkipped { .. } => "FileSkipped",
+				Event::FileDone { .. } => "FileDone",
+				Event::FileFailed { .. } => "FileFailed",
+				Event::End { .. } => "End",
+			};
+			events.lock().unwrap().push(name);
+		};
+		run_phases(vec![(Phase::Client, vec![task])], &[], &[], &DownloadOptions::default(), &reporter).unwrap();
+		assert_eq!(events.into_inner().unwrap(), ["Start", "PhaseStart", "FileSkipped", "PhaseEnd", "End"]);
+		fs::remove_dir_all(root).unwrap();
+	}
+
+	#[test]
+	fn legacy_assets_layouts() {
+		let root = file::temp_dir("legacy-assets");
+		let hash = "2aae6c35c94fcfb415dbe95f408b9ce91ee846ed";
+		fs::create_dir_all(path!(&root, "assets", "objects", "2a")).unwrap();
+		fs::write(path!(&root, "assets", "objects", "2a", hash), "hello world").unwrap();
+		let asset = api::assets::Asset {
+			hash: hash.to_string(),
+			size: 11,
+		};
+		let mut assets = api::assets::Assets {
+			objects: [("sounds/step.ogg".to_string(), asset.clone()), ("../../escape".to_string(), asset)].into(),
+			r#virtual: true,
+			map_to_resources: false,
+		};
+
+		materialize_legacy_assets(&assets, "legacy", &root).unwrap();
+		let step = path!(&root, "assets", "virtual", "legacy", "sounds", "step.ogg");
+		assert_eq!(fs::read_to_string(step).unwrap(), "hello world");
+		assert!(!path!(&root, "assets", "escape").exists());
+
+		(assets.r#virtual, assets.map_to_resources) = (false, true);
+		materialize_legacy_assets(&assets, "pre-1.6", &root).unwrap();
+		assert_eq!(
+			fs::read_to_string(path!(&root, "resources", "sounds", "step.ogg")).unwrap(),
+			"hello world"
+		);
+
+		assets.map_to_resources = false;
+		assert_eq!(legacy_assets_directory(&assets, "1.20", &root), None);
+		fs::remove_dir_all(root).unwrap();
+	}
+
+	#[test]
+	fn install_plan_totals() {
+		let file = |size, lzma: Option<usize>, valid| PlannedFile {
+			phase: Phase::Java,
+			task: Task {
+				resource: net::Resource::Java,
+				url: String::new(),
+				path: path::PathBuf::new(),
+				sha1: String::new(),
+				size,
+				executable: false,
+				lzma: lzma.map(|size| api::java::Download {
+					sha1: String::new(),
+					size,
+					url: String::new(),
+				}),
+			},
+			valid,
+		};
+		let plan = InstallPlan {
+			version: "1.20.1".to_string(),
+			files: vec![file(100, None, true), file(200, Some(50), false), file(300, None, false)],
+			links: Vec::new(),
+		};
+		assert_eq!(plan.total_bytes(), 450);
+		assert_eq!(plan.missing_bytes(), 350);
+		assert_eq!(plan.missing().count(), 2);
+	}
+
+	#[test]
+	fn retries_report_progress_once() {
+		let task = Task {
+			resource: net::Resource::Asset,
+			url: String::new(),
+			path: path!("file"),
+			sha1: String::new(),
+			size: 10,
+			executable: false,
+			lzma: None,
+		};
+		let total = atomic::AtomicU64::new(0);
+		let reporter = |event: Event<'_>| {
+			if let Event::Progress { bytes, .. } = event {
+				total.fetch_add(bytes, atomic::Ordering::Relaxed);
+			}
+		};
+		let progress = Progress::new(&task, &reporter);
+		// a first attempt fails after 6 bytes, the retry starts over and the fallback reports a larger file
+		for position in [4, 6, 3, 6, 8, 4, 12] {
+			progress.reach(position);
+		}
+		assert_eq!(total.into_inner(), 10);
+	}
+
+	#[test]
+	fn link_target_stays_inside_root() {
+		let links = ["jre.bundle/Contents/Home/bin/java", "d", "d/e"]
+			.into_iter()
+			.map(path::PathBuf::from)
+			.collect();
+		let link = path!("jre.bundle", "Contents", "Home", "bin", "java");
+		assert!(link_target(&link, "../../MacOS/libjli.dylib", &links).is_ok());
+		assert!(link_target(&link, "../../../../../other/java", &links).is_err());
+		assert!(link_target(&link, "/usr/bin/java", &links).is_err());
+		assert!(link_target(&path!("lib", "libjava.so"), "../d/../../x", &links).is_err());
+
+		// `d -> .` followed by `d/e -> ../../x` would escape through `d`
+		assert!(link_target(&path!("d"), ".", &links).is_ok());
+		assert!(entry_path("d/e", &links).is_err());
+		assert!(entry_path("../outside", &links).is_err());
+		assert!(entry_path("/etc/passwd", &links).is_err());
+		assert_eq!(entry_path("bin/java", &links).unwrap(), path!("bin", "java"));
+	}
+}
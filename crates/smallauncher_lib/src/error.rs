@@ -4,12 +4,22 @@ pub type Result<T, E = Error> = core::result::Result<T, E>;
 pub enum Error {
 	VersionNotFound,
 	JavaVersionNotSupported,
+	ServerNotAvailable,
+	EulaNotAccepted,
+	InheritanceCycle(Vec<String>),
+	InvalidCoordinate(String),
+	PreLaunchHook(String, std::process::ExitStatus),
 	Serialize(serde_json::Error),
 	Io(std::io::Error),
 	Network(ureq::Error),
 	OsString(std::ffi::OsString),
 	Zip(zip::result::ZipError),
+	Lzma(lzma_rs::error::Error),
 	ParseURL(oauth2::url::ParseError),
+	Integrity(std::path::PathBuf),
+	InvalidPath(std::path::PathBuf),
+	InvalidLink(std::path::PathBuf),
+	Download(Vec<(std::path::PathBuf, Error)>),
 }
 
 impl From<ureq::Error> for Error {
@@ -47,6 +57,13 @@ impl From<zip::result::ZipError> for Error {
 	}
 }
 
+impl From<lzma_rs::error::Error> for Error {
+	#[inline(always)]
+	fn from(value: lzma_rs::error::Error) -> Self {
+		Self::Lzma(value)
+	}
+}
+
 impl From<oauth2::url::ParseError> for Error {
 	#[inline(always)]
 	fn from(value: oauth2::url::ParseError) -> Self {
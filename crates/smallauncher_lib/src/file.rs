@@ -10,6 +10,25 @@ pub(crate) fn create_or_open_file<S: AsRef<ffi::OsStr>>(path: &S) -> Result<fs::
 	Ok(fs::File::create(&path)?)
 }
 
+/// sibling file where a download is written before being moved into `path`
+pub(crate) fn part_path<P: AsRef<path::Path>>(path: &P) -> path::PathBuf {
+	let mut name = path.as_ref().file_name().map(ffi::OsString::from).unwrap_or_default();
+	name.push(".part");
+	path.as_ref().with_file_name(name)
+}
+
+pub(crate) fn open_part_file<P: AsRef<path::Path>>(path: &P, append: bool) -> Result<fs::File, io::Error> {
+	if let Some(parent) = path.as_ref().parent() {
+		fs::create_dir_all(parent)?;
+	}
+	fs::OpenOptions::new()
+		.create(true)
+		.write(true)
+		.append(append)
+		.truncate(!append)
+		.open(path)
+}
+
 pub(crate) fn file_hash<P: AsRef<path::Path>>(hash: &str, path: &P) -> Result<bool, error::Error> {
 	use sha1::Digest;
 	let mut file = fs::File::open(path)?;
@@ -19,6 +38,30 @@ pub(crate) fn file_hash<P: AsRef<path::Path>>(hash: &str, path: &P) -> Result<bo
 	Ok(reader_hash == hash)
 }
 
+/// resolves `.` and `..` components without touching the file system
+pub(crate) fn normalize(path: &path::Path) -> path::PathBuf {
+	let mut normalized = path::PathBuf::new();
+	for component in path.components() {
+		match component {
+			path::Component::CurDir => {}
+			path::Component::ParentDir => {
+				normalized.pop();
+			}
+			component => normalized.push(component),
+		}
+	}
+	normalized
+}
+
+/// empty directory in the system temporary directory, unique to `name` and the test process
+#[cfg(test)]
+pub(crate) fn temp_dir(name: &str) -> path::PathBuf {
+	let path = path!(std::env::temp_dir(), format!("smallauncher-{name}-{}", std::process::id()));
+	let _ = fs::remove_dir_all(&path);
+	fs::create_dir_all(&path).unwrap();
+	path
+}
+
 #[inline(always)]
 pub(crate) fn from_json_file<T: serde::de::DeserializeOwned, P: AsRef<path::Path>>(path: P) -> Result<T, error::Error> {
 	Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
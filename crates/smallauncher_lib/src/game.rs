@@ -0,0 +1,179 @@
+use crate::*;
+
+use std::io;
+use std::io::BufRead;
+use std::path;
+use std::process;
+use std::sync::mpsc;
+use std::thread;
+use std::time;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+	Stdout,
+	Stderr,
+}
+
+/// line printed by the game, without the line ending
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputLine {
+	pub stream: Stream,
+	pub text: String,
+}
+
+/// running game started by `launch::launch_minecraft_version`, the post-exit hooks only run through `wait`
+#[derive(Debug)]
+pub struct GameProcess {
+	child: process::Child,
+	lines: Option<mpsc::Receiver<OutputLine>>,
+	readers: Vec<thread::JoinHandle<()>>,
+	game_path: path::PathBuf,
+	natives_path: path::PathBuf,
+	started: time::SystemTime,
+	post_exit: Vec<String>,
+	hook_environment: hook::HookEnvironment,
+}
+
+fn read_lines(reader: impl io::Read + Send + 'static, stream: Stream, sender: mpsc::Sender<OutputLine>) -> thread::JoinHandle<()> {
+	thread::spawn(move || {
+		let mut reader = io::BufReader::new(reader);
+		let mut line = Vec::new();
+		loop {
+			line.clear();
+			match reader.read_until(b'\n', &mut line) {
+				Ok(0) => break,
+				Ok(_) => {
+					let text = String::from_utf8_lossy(&line).trim_end_matches(['\r', '\n']).to_string();
+					if sender.send(OutputLine { stream, text }).is_err() {
+						break;
+					}
+				}
+				Err(e) => {
+					log::warn!("Failed to read the game {stream:?}: {e}");
+					break;
+				}
+			}
+		}
+	})
+}
+
+impl GameProcess {
+	/// takes over `child`, its output is captured when it was spawned with piped stdout and stderr
+	pub(crate) fn new(mut child: process::Child, game_path: path::PathBuf, natives_path: path::PathBuf) -> Self {
+		let mut readers = Vec::new();
+		let (sender, receiver) = mpsc::channel();
+		if let Some(stdout) = child.stdout.take() {
+			readers.push(read_lines(stdout, Stream::Stdout, sender.clone()));
+		}
+		if let Some(stderr) = child.stderr.take() {
+			readers.push(read_lines(stderr, Stream::Stderr, sender));
+		}
+		Self {
+			lines: (!readers.is_empty()).then_some(receiver),
+			child,
+			readers,
+			game_path,
+			natives_path,
+			started: time::SystemTime::now(),
+			post_exit: Vec::new(),
+			hook_environment: hook::HookEnvironment::default(),
+		}
+	}
+
+	/// hooks run by `wait` once the game exits
+	pub(crate) fn with_post_exit(mut self, post_exit: Vec<String>, hook_environment: hook::HookEnvironment) -> Self {
+		self.post_exit = post_exit;
+		self.hook_environment = hook_environment;
+		self
+	}
+
+	#[inline(always)]
+	pub fn id(&self) -> u32 {
+		self.child.id()
+	}
+
+	#[inline(always)]
+	pub fn natives_path(&self) -> &path::Path {
+		&self.natives_path
+	}
+
+	/// lines of stdout and stderr in the order they were read, `None` unless the output is captured.
+	/// iterating ends once the game closes both streams
+	#[inline(always)]
+	pub fn lines(&self) -> Option<&mpsc::Receiver<OutputLine>> {
+		self.lines.as_ref()
+	}
+
+	/// waits for the game to exit, removes its natives directory and runs the post-exit hooks
+	pub fn wait(&mut self) -> Result<process::ExitStatus, error::Error> {
+		let status = self.child.wait()?;
+		for reader in self.readers.drain(..) {
+			let _ = reader.join();
+		}
+		if let Err(e) = std::fs::remove_dir_all(&self.natives_path) {
+			log::warn!("Failed to remove natives {:?}: {e}", self.natives_path);
+		}
+		let environment = hook::HookEnvironment {
+			exit_code: status.code(),
+			..self.hook_environment.clone()
+		};
+		for command in std::mem::take(&mut self.post_exit) {
+			match hook::run_hook(&command, &environment) {
+				Ok(hook_status) if !hook_status.success() => log::warn!("Post-exit hook {command:?} failed: {hook_status}"),
+				Ok(_) => {}
+				Err(e) => log::warn!("Failed to run post-exit hook {command:?}: {e:?}"),
+			}
+		}
+		Ok(status)
+	}
+
+	/// exit status of the game if it has already exited
+	#[inline(always)]
+	pub fn try_wait(&mut self) -> Result<Option<process::ExitStatus>, error::Error> {
+		Ok(self.child.try_wait()?)
+	}
+
+	#[inline(always)]
+	pub fn kill(&mut self) -> Result<(), error::Error> {
+		Ok(self.child.kill()?)
+	}
+
+	/// newest crash report or jvm fatal error log written since the game was launched
+	#[inline(always)]
+	pub fn crash(&self) -> Option<crash::Crash> {
+		crash::find_crash(&self.game_path, self.started)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[cfg(target_family = "unix")]
+	#[test]
+	fn captured_output_and_exit_code() {
+		let child = process::Command::new("sh")
+			.args(["-c", "echo out; echo err >&2; exit 3"])
+			.stdout(process::Stdio::piped())
+			.stderr(process::Stdio::piped())
+			.spawn()
+			.unwrap();
+		let mut game = GameProcess::new(child, std::env::temp_dir(), path!(std::env::temp_dir(), "smallauncher-natives-test"));
+		let mut lines: Vec<OutputLine> = game.lines().unwrap().iter().collect();
+		lines.sort_by_key(|line| line.text.clone());
+		assert_eq!(
+			lines,
+			[
+				OutputLine {
+					stream: Stream::Stderr,
+					text: "err".to_string()
+				},
+				OutputLine {
+					stream: Stream::Stdout,
+					text: "out".to_string()
+				},
+			]
+		);
+		assert_eq!(game.wait().unwrap().code(), Some(3));
+	}
+}
@@ -0,0 +1,269 @@
+use std::fmt;
+use std::str;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+	Trace,
+	Debug,
+	Info,
+	Warn,
+	Error,
+	Fatal,
+}
+
+impl str::FromStr for Level {
+	type Err = ();
+
+	fn from_str(level: &str) -> Result<Self, Self::Err> {
+		match level.to_ascii_uppercase().as_str() {
+			"TRACE" | "FINEST" | "FINER" => Ok(Self::Trace),
+			"DEBUG" | "FINE" => Ok(Self::Debug),
+			"INFO" | "CONFIG" => Ok(Self::Info),
+			"WARN" | "WARNING" => Ok(Self::Warn),
+			"ERROR" => Ok(Self::Error),
+			"FATAL" | "SEVERE" => Ok(Self::Fatal),
+			_ => Err(()),
+		}
+	}
+}
+
+impl fmt::Display for Level {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Trace => f.write_str("TRACE"),
+			Self::Debug => f.write_str("DEBUG"),
+			Self::Info => f.write_str("INFO"),
+			Self::Warn => f.write_str("WARN"),
+			Self::Error => f.write_str("ERROR"),
+			Self::Fatal => f.write_str("FATAL"),
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Timestamp {
+	/// milliseconds since the unix epoch, from log4j events
+	Unix(u64),
+	/// time as printed by the game in plain lines
+	Text(String),
+}
+
+impl fmt::Display for Timestamp {
+	/// time of day in UTC for unix timestamps
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Unix(millis) => {
+				let seconds = millis / 1000 % (24 * 60 * 60);
+				write!(f, "{:02}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+			}
+			Self::Text(text) => f.write_str(text),
+		}
+	}
+}
+
+/// line or event of the game log
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogRecord {
+	pub timestamp: Option<Timestamp>,
+	pub level: Level,
+	pub thread: Option<String>,
+	pub logger: Option<String>,
+	pub message: String,
+	/// stack trace attached to the event
+	pub throwable: Option<String>,
+}
+
+impl fmt::Display for LogRecord {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		if let Some(timestamp) = &self.timestamp {
+			write!(f, "[{timestamp}] ")?;
+		}
+		match &self.thread {
+			Some(thread) => write!(f, "[{thread}/{}]: {}", self.level, self.message)?,
+			None => write!(f, "[{}]: {}", self.level, self.message)?,
+		}
+		if let Some(throwable) = &self.throwable {
+			write!(f, "\n{}", throwable.trim_end())?;
+		}
+		Ok(())
+	}
+}
+
+/// turns the lines printed by the game into records, log4j xml events may span several lines
+#[derive(Debug, Clone, Default)]
+pub struct LogParser {
+	event: String,
+	/// level of the last record, given to the continuation lines of a stack trace
+	level: Option<Level>,
+}
+
+impl LogParser {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// parses one line of output, `None` while an xml event is still incomplete
+	pub fn push(&mut self, line: &str) -> Option<LogRecord> {
+		if self.event.is_empty() && !line.trim_start().starts_with("<log4j:Event") {
+			let record = self.parse_line(line);
+			self.level = Some(record.level);
+			return Some(record);
+		}
+		self.event.push_str(line);
+		self.event.push('\n');
+		if !line.contains("</log4j:Event>") {
+			return None;
+		}
+		let event = std::mem::take(&mut self.event);
+		let record = parse_event(&event).unwrap_or_else(|| self.parse_line(event.trim_end()));
+		self.level = Some(record.level);
+		Some(record)
+	}
+
+	/// the incomplete event left when the output ends
+	pub fn finish(&mut self) -> Option<LogRecord> {
+		if self.event.is_empty() {
+			return None;
+		}
+		let event = std::mem::take(&mut self.event);
+		Some(self.parse_line(event.trim_end()))
+	}
+
+	/// `[12:34:56] [Render thread/INFO]: message`, `2013-05-01 12:34:56 [INFO] message` or any other line
+	fn parse_line(&self, line: &str) -> LogRecord {
+		let mut record = LogRecord {
+			timestamp: None,
+			level: Level::Info,
+			thread: None,
+			logger: None,
+			message: line.to_string(),
+			throwable: None,
+		};
+		if let Some((time, rest)) = line.strip_prefix('[').and_then(|rest| rest.split_once("] [")) {
+			if let Some((thread, message)) = rest.split_once("]: ") {
+				let (thread, level) = thread.rsplit_once('/').unwrap_or(("", thread));
+				if let Ok(level) = level.parse() {
+					record.timestamp = Some(Timestamp::Text(time.to_string()));
+					record.level = level;
+					record.thread = (!thread.is_empty()).then(|| thread.to_string());
+					record.message = message.to_string();
+					return record;
+				}
+			}
+		}
+		if let Some((time, rest)) = line.split_once(" [") {
+			if let Some((level, message)) = rest.split_once("] ") {
+				if let Ok(level) = level.parse() {
+					record.timestamp = (!time.is_empty()).then(|| Timestamp::Text(time.to_string()));
+					record.level = level;
+					record.message = message.to_string();
+					return record;
+				}
+			}
+		}
+		let continuation = line.starts_with(char::is_whitespace) || line.starts_with("Caused by: ");
+		if let (true, Some(level)) = (continuation, self.level) {
+			record.level = level;
+		}
+		record
+	}
+}
+
+/// parses a complete `<log4j:Event>` element
+fn parse_event(event: &str) -> Option<LogRecord> {
+	use quick_xml::events::Event;
+
+	let mut reader = quick_xml::Reader::from_str(event);
+	let mut record = LogRecord {
+		timestamp: None,
+		level: Level::Info,
+		thread: None,
+		logger: None,
+		message: String::new(),
+		throwable: None,
+	};
+	let mut element = Vec::new();
+	loop {
+		match reader.read_event().ok()? {
+			Event::Start(start) => {
+				element = start.name().as_ref().to_vec();
+				if element != b"log4j:Event" {
+					continue;
+				}
+				for attribute in start.attributes() {
+					let attribute = attribute.ok()?;
+					let value = attribute.unescape_value().ok()?.into_owned();
+					match attribute.key.as_ref() {
+						b"logger" => record.logger = Some(value),
+						b"timestamp" => record.timestamp = value.parse().ok().map(Timestamp::Unix),
+						b"level" => record.level = value.parse().unwrap_or(Level::Info),
+						b"thread" => record.thread = Some(value),
+						_ => {}
+					}
+				}
+			}
+			Event::CData(data) => {
+				let text = data.decode().ok()?;
+				match element.as_slice() {
+					b"log4j:Message" => record.message.push_str(&text),
+					b"log4j:Throwable" => record.throwable.get_or_insert_with(String::new).push_str(&text),
+					_ => {}
+				}
+			}
+			Event::Text(text) => {
+				let text = text.unescape().ok()?;
+				match element.as_slice() {
+					b"log4j:Message" => record.message.push_str(&text),
+					b"log4j:Throwable" => record.throwable.get_or_insert_with(String::new).push_str(&text),
+					_ => {}
+				}
+			}
+			Event::End(end) if end.name().as_ref() == b"log4j:Event" => return Some(record),
+			Event::End(_) => element.clear(),
+			Event::Eof => return None,
+			_ => {}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parse_xml_and_plain_lines() {
+		let mut parser = LogParser::new();
+		let lines = [
+			r#"<log4j:Event logger="net.minecraft.client.Minecraft" timestamp="1700000000000" level="ERROR" thread="Render thread">"#,
+			r#"  <log4j:Message><![CDATA[Failed to load <texture>]]></log4j:Message>"#,
+			r#"  <log4j:Throwable><![CDATA[java.io.IOException: missing"#,
+			"\tat net.minecraft.Foo.bar(Foo.java:1)",
+			r#"]]></log4j:Throwable>"#,
+			r#"</log4j:Event>"#,
+		];
+		let records: Vec<LogRecord> = lines.iter().filter_map(|line| parser.push(line)).collect();
+		assert_eq!(
+			records,
+			[LogRecord {
+				timestamp: Some(Timestamp::Unix(1700000000000)),
+				level: Level::Error,
+				thread: Some("Render thread".to_string()),
+				logger: Some("net.minecraft.client.Minecraft".to_string()),
+				message: "Failed to load <texture>".to_string(),
+				throwable: Some("java.io.IOException: missing\n\tat net.minecraft.Foo.bar(Foo.java:1)\n".to_string()),
+			}]
+		);
+		assert_eq!(records[0].timestamp.as_ref().unwrap().to_string(), "22:13:20");
+
+		let record = parser.push("[12:34:56] [Server thread/WARN]: Can't keep up!").unwrap();
+		assert_eq!(record.level, Level::Warn);
+		assert_eq!(record.thread.as_deref(), Some("Server thread"));
+		assert_eq!(record.message, "Can't keep up!");
+
+		let record = parser.push("2013-05-01 12:34:56 [SEVERE] Unable to launch").unwrap();
+		assert_eq!(record.level, Level::Fatal);
+		assert_eq!(record.timestamp, Some(Timestamp::Text("2013-05-01 12:34:56".to_string())));
+		assert_eq!(parser.push("\tat net.minecraft.client.Minecraft.run").unwrap().level, Level::Fatal);
+		assert_eq!(parser.push("Setting user: Steve").unwrap().level, Level::Info);
+	}
+}
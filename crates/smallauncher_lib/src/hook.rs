@@ -0,0 +1,62 @@
+use crate::*;
+
+use std::path;
+use std::process;
+
+/// what a hook is told about the launch through `SMALLAUNCHER_*` environment variables
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HookEnvironment {
+	/// `SMALLAUNCHER_VERSION`
+	pub version: String,
+	/// `SMALLAUNCHER_GAME_DIR`
+	pub game_directory: path::PathBuf,
+	/// `SMALLAUNCHER_ACCOUNT`
+	pub account: String,
+	/// `SMALLAUNCHER_EXIT_CODE`, only set for post-exit hooks and missing when the game was killed by a signal
+	pub exit_code: Option<i32>,
+}
+
+/// runs `command` with the system shell in the game directory and waits for it
+pub fn run_hook(command: &str, environment: &HookEnvironment) -> Result<process::ExitStatus, error::Error> {
+	#[cfg(target_family = "unix")]
+	let mut shell = {
+		let mut shell = process::Command::new("sh");
+		shell.arg("-c").arg(command);
+		shell
+	};
+	#[cfg(target_family = "windows")]
+	let mut shell = {
+		let mut shell = process::Command::new("cmd");
+		shell.arg("/C").arg(command);
+		shell
+	};
+	shell
+		.current_dir(&environment.game_directory)
+		.env("SMALLAUNCHER_VERSION", &environment.version)
+		.env("SMALLAUNCHER_GAME_DIR", &environment.game_directory)
+		.env("SMALLAUNCHER_ACCOUNT", &environment.account);
+	if let Some(exit_code) = environment.exit_code {
+		shell.env("SMALLAUNCHER_EXIT_CODE", exit_code.to_string());
+	}
+	log::info!("Running hook: {command}");
+	Ok(shell.status()?)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[cfg(target_family = "unix")]
+	#[test]
+	fn hook_environment() {
+		let environment = HookEnvironment {
+			version: "1.20.1".to_string(),
+			game_directory: std::env::temp_dir(),
+			account: "Steve".to_string(),
+			exit_code: Some(1),
+		};
+		let command = r#"test "$SMALLAUNCHER_VERSION:$SMALLAUNCHER_ACCOUNT:$SMALLAUNCHER_EXIT_CODE" = "1.20.1:Steve:1""#;
+		assert!(run_hook(command, &environment).unwrap().success());
+		assert!(!run_hook("exit 2", &environment).unwrap().success());
+	}
+}
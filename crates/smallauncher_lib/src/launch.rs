@@ -1,10 +1,12 @@
 use crate::*;
 
+use std::collections;
 use std::ffi;
 use std::fs;
 use std::io;
 use std::path;
 use std::process;
+use std::time;
 
 const CLIENT_ID: &'static str = "42";
 const LAUNCHER_NAME: &'static str = "miniLauncher";
@@ -13,6 +15,110 @@ const LAUNCHER_VERSION: &'static str = "42";
 #[cfg(all(not(debug_assertions), target_os = "windows"))]
 const DETACHED_PROCESS: u32 = 0x00000008;
 
+/// disables the message lookups exploited by log4shell, for versions with an old log4j-core
+const LOG4J_NO_LOOKUPS: &str = "-Dlog4j2.formatMsgNoLookups=true";
+
+/// natives directories without an owner older than this are left over from previous launches
+const STALE_NATIVES: time::Duration = time::Duration::from_secs(24 * 60 * 60);
+
+/// file of a natives directory holding the pid of the process using it
+const NATIVES_OWNER: &str = ".owner";
+
+/// prefix of the natives directories of exported launches, which `clean_natives` leaves alone
+const EXPORTED_NATIVES: &str = "export-";
+
+/// port of servers given without one
+const DEFAULT_PORT: u16 = 25565;
+
+/// window size used when only one side of the resolution is set
+const DEFAULT_WIDTH: u32 = 854;
+const DEFAULT_HEIGHT: u32 = 480;
+
+/// where the game goes once it has started
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QuickPlay {
+	/// name of the world directory in `saves`
+	Singleplayer(String),
+	/// `host[:port]`
+	Multiplayer(String),
+	/// id of the realm
+	Realms(String),
+}
+
+impl QuickPlay {
+	/// game argument of this destination in versions with quick play
+	fn argument(&self) -> &'static str {
+		match self {
+			Self::Singleplayer(_) => "--quickPlaySingleplayer",
+			Self::Multiplayer(_) => "--quickPlayMultiplayer",
+			Self::Realms(_) => "--quickPlayRealms",
+		}
+	}
+}
+
+/// host and port of `address`, `[::1]:25565` style for ipv6
+fn split_address(address: &str) -> (&str, u16) {
+	if let Some(rest) = address.strip_prefix('[') {
+		if let Some((host, rest)) = rest.split_once(']') {
+			let port = rest.strip_prefix(':').and_then(|port| port.parse().ok());
+			return (host, port.unwrap_or(DEFAULT_PORT));
+		}
+	}
+	match address.rsplit_once(':') {
+		Some((host, port)) if !host.contains(':') => (host, port.parse().unwrap_or(DEFAULT_PORT)),
+		_ => (address, DEFAULT_PORT),
+	}
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LaunchOptions {
+	/// initial heap size in MiB (`-Xms`)
+	pub min_memory: Option<u32>,
+	/// maximum heap size in MiB (`-Xmx`)
+	pub max_memory: Option<u32>,
+	/// added before the jvm arguments of the version
+	pub jvm_args: Vec<String>,
+	/// `java` executable used instead of the mojang runtime of the version
+	pub java_path: Option<path::PathBuf>,
+	/// window width, enables the `has_custom_resolution` feature
+	pub width: Option<u32>,
+	/// window height, enables the `has_custom_resolution` feature
+	pub height: Option<u32>,
+	pub fullscreen: bool,
+	/// added to the launch variables, overriding the ones of the launcher
+	pub variables: variables::Variables,
+	/// pipes stdout and stderr to `game::GameProcess::lines` instead of inheriting them
+	pub capture_output: bool,
+	/// enables the `is_quick_play_*` feature of the destination, older versions fall back to `--server` and `--port`
+	pub quick_play: Option<QuickPlay>,
+	/// commands the game is run through, the first one runs the next one and the last one runs java,
+	/// for example `gamemoderun` and `mangohud --dlsym`
+	pub wrappers: Vec<Vec<String>>,
+	/// environment of the game process on top of the launcher one, `None` removes the variable
+	pub environment: collections::BTreeMap<String, Option<String>>,
+	/// shell commands run in order once the version is loaded and before anything else, the launch is aborted if one fails
+	pub pre_launch: Vec<String>,
+	/// shell commands run in order once `game::GameProcess::wait` sees the game exit,
+	/// they never run if the `game::GameProcess` is dropped without calling `wait`
+	pub post_exit: Vec<String>,
+}
+
+impl LaunchOptions {
+	#[inline(always)]
+	pub fn has_custom_resolution(&self) -> bool {
+		self.width.is_some() || self.height.is_some()
+	}
+	pub fn features(&self) -> rules::Features {
+		rules::Features {
+			has_custom_resolution: self.has_custom_resolution(),
+			is_quick_play_singleplayer: matches!(self.quick_play, Some(QuickPlay::Singleplayer(_))),
+			is_quick_play_multiplayer: matches!(self.quick_play, Some(QuickPlay::Multiplayer(_))),
+			is_quick_play_realms: matches!(self.quick_play, Some(QuickPlay::Realms(_))),
+			..Default::default()
+		}
+	}
+}
+
 fn merge(a: serde_json::Value, b: serde_json::Value) -> serde_json::Value {
 	match (a, b) {
 		(serde_json::Value::Object(mut a), serde_json::Value::Object(b)) => {
@@ -33,52 +139,256 @@ fn merge(a: serde_json::Value, b: serde_json::Value) -> serde_json::Value {
 	}
 }
 
+/// libraries with the same key are the same library in different versions
+fn library_key(name: &str) -> String {
+	name.parse::<maven::Coordinate>()
+		.map_or_else(|_| name.to_string(), |coordinate| coordinate.key())
+}
+
+/// merges `child` into the version it inherits from, the libraries of the child come first and replace the ones of `parent` with the same key
+fn merge_version(
+	mut parent: serde_json::Map<String, serde_json::Value>,
+	mut child: serde_json::Map<String, serde_json::Value>,
+) -> serde_json::Map<String, serde_json::Value> {
+	let libraries = |map: &mut serde_json::Map<String, serde_json::Value>| match map.remove("libraries") {
+		Some(serde_json::Value::Array(libraries)) => libraries,
+		_ => Vec::new(),
+	};
+	let mut merged = libraries(&mut child);
+	let keys: collections::HashSet<String> = merged
+		.iter()
+		.filter_map(|library| library.get("name")?.as_str().map(library_key))
+		.collect();
+	merged.extend(libraries(&mut parent).into_iter().filter(|library| {
+		let key = library.get("name").and_then(serde_json::Value::as_str).map(library_key);
+		!key.is_some_and(|key| keys.contains(&key))
+	}));
+
+	let serde_json::Value::Object(mut version) = merge(serde_json::Value::Object(parent), serde_json::Value::Object(child)) else {
+		unreachable!("merging two objects");
+	};
+	version.insert("libraries".to_string(), serde_json::Value::Array(merged));
+	version
+}
+
+/// loads `version_name` with every version of its `inheritsFrom` chain merged into it
+pub fn load_version(game_path: &path::Path, version_name: &str) -> Result<api::meta::Version, error::Error> {
+	let mut chain: Vec<String> = Vec::new();
+	let mut versions = Vec::new();
+	let mut name = version_name.to_string();
+	loop {
+		if chain.contains(&name) {
+			chain.push(name);
+			return Err(error::Error::InheritanceCycle(chain));
+		}
+		let meta_path = path!(game_path, "versions", &name, format!("{name}.json"));
+		let mut version: serde_json::Map<String, serde_json::Value> = file::from_json_file(&meta_path)?;
+		let parent = version.remove("inheritsFrom");
+		chain.push(name);
+		versions.push(version);
+		match parent {
+			Some(serde_json::Value::String(parent)) => name = parent,
+			_ => break,
+		}
+	}
+	let version = versions.into_iter().rev().reduce(merge_version).unwrap_or_default();
+	Ok(serde_json::from_value(serde_json::Value::Object(version))?)
+}
+
 pub fn launch_minecraft_version(
 	game_path: &path::Path,
 	jre_path: &path::Path,
 	version_name: &str,
 	account: &auth::Account,
-) -> Result<(), error::Error> {
-	let meta: api::meta::Version = {
-		let meta_path = path!(game_path, "versions", &version_name, format!("{version_name}.json"));
-		let mut map1: serde_json::Map<String, serde_json::Value> = file::from_json_file(&meta_path)?;
-		match map1.remove("inheritsFrom") {
-			Some(serde_json::Value::String(version_name)) => {
-				let meta_path = path!(game_path, "versions", &version_name, format!("{version_name}.json"));
-				let map2: serde_json::Map<String, serde_json::Value> = file::from_json_file(&meta_path)?;
-				let v1 = serde_json::Value::Object(map1);
-				let v2 = serde_json::Value::Object(map2);
-				serde_json::from_value(merge(v2, v1))?
-			}
-			_ => serde_json::from_value(serde_json::Value::Object(map1))?,
-		}
+	options: &LaunchOptions,
+) -> Result<game::GameProcess, error::Error> {
+	// a mistyped version must fail before the hooks have any side effect
+	let meta = load_version(game_path, version_name)?;
+	let hook_environment = hook::HookEnvironment {
+		version: version_name.to_string(),
+		game_directory: game_path.to_path_buf(),
+		account: match account {
+			auth::Account::Microsoft { name, .. } => name.clone(),
+			auth::Account::Offline { name, .. } => name.clone(),
+		},
+		exit_code: None,
 	};
+	for command in &options.pre_launch {
+		let status = hook::run_hook(command, &hook_environment)?;
+		if !status.success() {
+			return Err(error::Error::PreLaunchHook(command.clone(), status));
+		}
+	}
+
+	let natives_path = prepare_natives(&meta, game_path)?;
+	let plan = plan_launch(&meta, game_path, jre_path, natives_path, account, options);
+	let mut command = plan.command();
+	#[cfg(all(not(debug_assertions), target_os = "windows"))]
+	std::os::windows::process::CommandExt::creation_flags(&mut command, DETACHED_PROCESS);
+	if options.capture_output {
+		command.stdout(process::Stdio::piped()).stderr(process::Stdio::piped());
+	}
+
+	// the arguments and the environment of the command hold the access token
+	let mut logged = plan.clone();
+	logged.redact();
+	log::info!("Spawning command: {:?}", logged.program());
+	let child = command.spawn()?;
+	if let Err(e) = own_natives(&plan.natives_directory, child.id()) {
+		log::warn!("Failed to record the owner of {:?}: {e:?}", plan.natives_directory);
+	}
+	let game = game::GameProcess::new(child, game_path.to_path_buf(), plan.natives_directory);
+	Ok(game.with_post_exit(options.post_exit.clone(), hook_environment))
+}
+
+/// loads `version_name` and extracts its natives into the natives directory of its exports, replacing the ones of a previous export.
+/// the returned plan is ready to be run by other tooling and is the same for every export of the same launch
+pub fn export_launch(
+	game_path: &path::Path,
+	jre_path: &path::Path,
+	version_name: &str,
+	account: &auth::Account,
+	options: &LaunchOptions,
+) -> Result<plan::LaunchPlan, error::Error> {
+	let meta = load_version(game_path, version_name)?;
+	let natives_path = exported_natives_directory(game_path, &meta.id);
+	if natives_path.exists() {
+		// unlinked rather than overwritten, a running export keeps its libraries mapped
+		fs::remove_dir_all(&natives_path)?;
+	}
+	extract_natives(&meta, game_path, &natives_path)?;
+	Ok(plan_launch(&meta, game_path, jre_path, natives_path, account, options))
+}
 
-	extract_natives(&meta, &game_path)?;
+/// extracts the natives of `meta` into a new natives directory owned by the launcher until the game is spawned
+fn prepare_natives(meta: &api::meta::Version, game_path: &path::Path) -> Result<path::PathBuf, error::Error> {
+	clean_natives(game_path);
+	let natives_path = natives_directory(game_path, &meta.id);
+	extract_natives(meta, game_path, &natives_path)?;
+	own_natives(&natives_path, process::id())?;
+	Ok(natives_path)
+}
 
-	let main_class = &meta.main_class;
-	let java_component = &meta.java_version.component;
-	let jvm_args = generate_jvm_args(&meta, &game_path);
-	let game_args = generate_game_args(&meta, &game_path, account);
+/// everything the launch of `meta` runs, with its natives in `natives_path`
+pub fn plan_launch(
+	meta: &api::meta::Version,
+	game_path: &path::Path,
+	jre_path: &path::Path,
+	natives_path: path::PathBuf,
+	account: &auth::Account,
+	options: &LaunchOptions,
+) -> plan::LaunchPlan {
+	let variables = launch_variables(meta, game_path, &natives_path, account, options);
+	plan::LaunchPlan {
+		version: meta.id.clone(),
+		wrappers: options.wrappers.clone(),
+		java: match &options.java_path {
+			Some(java_path) => java_path.clone(),
+			None => java_binary(jre_path, &meta.java_version.component),
+		},
+		working_directory: game_path.to_path_buf(),
+		natives_directory: natives_path,
+		jvm_args: generate_jvm_args(meta, &variables, options),
+		main_class: meta.main_class.clone(),
+		game_args: generate_game_args(meta, &variables, options),
+		environment: options.environment.clone(),
+		access_token: variables.get("auth_access_token").unwrap_or_default().to_string_lossy().into_owned(),
+	}
+}
+
+/// `java` executable of the mojang runtime `java_component`
+pub(crate) fn java_binary(jre_path: &path::Path, java_component: &str) -> path::PathBuf {
 	#[cfg(target_family = "unix")]
 	let jre_bin = path!(jre_path, java_component, "bin", "java");
 	#[cfg(target_family = "windows")]
 	let jre_bin = path!(jre_path, java_component, "bin", "java.exe");
-	let mut command = process::Command::new(jre_bin);
-	#[cfg(all(not(debug_assertions), target_os = "windows"))]
-	std::os::windows::process::CommandExt::creation_flags(&mut command, DETACHED_PROCESS);
-	command.current_dir(game_path).args(jvm_args).arg(main_class).args(game_args);
+	jre_bin
+}
 
-	log::info!("Spawning command: {:?}", command);
-	command.spawn()?;
+/// directory of the natives of a single launch of `version_name`
+pub fn natives_directory(game_path: &path::Path, version_name: &str) -> path::PathBuf {
+	let millis = time::SystemTime::now().duration_since(time::UNIX_EPOCH).unwrap_or_default().as_millis();
+	path!(game_path, "natives", format!("{version_name}-{millis}"))
+}
+
+/// records `pid` as the process using `natives_path`, `clean_natives` keeps the directory while it runs
+pub fn own_natives(natives_path: &path::Path, pid: u32) -> Result<(), error::Error> {
+	fs::write(path!(natives_path, NATIVES_OWNER), pid.to_string())?;
 	Ok(())
 }
 
-pub fn extract_natives(meta: &api::meta::Version, game_path: &path::Path) -> Result<(), error::Error> {
+/// the process `pid` is running, a reused pid only keeps natives longer than needed
+#[cfg(target_os = "linux")]
+fn process_alive(pid: u32) -> bool {
+	path!("/proc", pid.to_string()).exists()
+}
+
+#[cfg(all(target_family = "unix", not(target_os = "linux")))]
+fn process_alive(pid: u32) -> bool {
+	process::Command::new("kill")
+		.args(["-0", &pid.to_string()])
+		.stderr(process::Stdio::null())
+		.status()
+		.is_ok_and(|status| status.success())
+}
+
+#[cfg(target_family = "windows")]
+fn process_alive(pid: u32) -> bool {
+	process::Command::new("tasklist")
+		.args(["/FI", &format!("PID eq {pid}"), "/NH", "/FO", "CSV"])
+		.output()
+		.is_ok_and(|output| String::from_utf8_lossy(&output.stdout).contains(&format!("\"{pid}\"")))
+}
+
+/// directory of the natives of the exported launches of `version_name`
+pub fn exported_natives_directory(game_path: &path::Path, version_name: &str) -> path::PathBuf {
+	path!(game_path, "natives", format!("{EXPORTED_NATIVES}{version_name}"))
+}
+
+/// removes the natives directories left over from previous launches: the ones whose owner has exited,
+/// and the ones without an owner that are older than `STALE_NATIVES`. the natives of exports are kept
+pub fn clean_natives(game_path: &path::Path) {
+	let Ok(entries) = fs::read_dir(path!(game_path, "natives")) else {
+		return;
+	};
+	for entry in entries.flatten() {
+		if entry.file_name().to_string_lossy().starts_with(EXPORTED_NATIVES) {
+			continue;
+		}
+		let owner = fs::read_to_string(path!(entry.path(), NATIVES_OWNER))
+			.ok()
+			.and_then(|pid| pid.trim().parse().ok());
+		let stale = match owner {
+			Some(pid) => !process_alive(pid),
+			None => entry
+				.metadata()
+				.and_then(|metadata| metadata.modified())
+				.is_ok_and(|modified| modified.elapsed().is_ok_and(|elapsed| elapsed > STALE_NATIVES)),
+		};
+		if !stale {
+			continue;
+		}
+		match fs::remove_dir_all(entry.path()) {
+			Ok(()) => log::info!("Removed stale natives {:?}", entry.path()),
+			Err(e) => log::warn!("Failed to remove stale natives {:?}: {e}", entry.path()),
+		}
+	}
+}
+
+/// extracts the native libraries of `meta` into `natives_path`, skipping the entries in the `extract.exclude` of each library
+pub fn extract_natives(meta: &api::meta::Version, game_path: &path::Path, natives_path: &path::Path) -> Result<(), error::Error> {
+	fs::create_dir_all(natives_path)?;
 	for library in &meta.libraries {
 		let Some(downloads) = &library.downloads else {
 			continue;
 		};
+		if library
+			.rules
+			.as_ref()
+			.is_some_and(|rules| !rules::is_allowed(rules, &rules::Features::default()))
+		{
+			continue;
+		}
 		let Some(classifier) = &downloads.classifiers else {
 			continue;
 		};
@@ -98,16 +408,24 @@ pub fn extract_natives(meta: &api::meta::Version, game_path: &path::Path) -> Res
 			continue;
 		};
 
+		let exclude = library.extract.as_ref().map(|extract| extract.exclude.as_slice()).unwrap_or_default();
 		let path = path!(game_path, "libraries", &library_download.path);
 		let file = fs::File::open(&path)?;
 		let mut zip = zip::ZipArchive::new(file)?;
 		for i in 0..zip.len() {
 			let mut zip_file = zip.by_index(i)?;
 			let name = zip_file.name();
-			if !(name.ends_with(".so") || name.ends_with(".dll") || name.ends_with(".dylib")) {
+			if !(name.ends_with(".so") || name.ends_with(".dll") || name.ends_with(".dylib") || name.ends_with(".jnilib")) {
 				continue;
 			}
-			let path = path!(game_path, "bin", name);
+			if exclude.iter().any(|prefix| name.starts_with(prefix.as_str())) {
+				continue;
+			}
+			let Some(name) = zip_file.enclosed_name() else {
+				log::warn!("Skipping native outside of {natives_path:?}: {}", zip_file.name());
+				continue;
+			};
+			let path = path!(natives_path, name);
 			log::info!("Extracting {path:?}");
 			let mut file = file::create_or_open_file(&path)?;
 			io::copy(&mut zip_file, &mut file)?;
@@ -116,102 +434,169 @@ pub fn extract_natives(meta: &api::meta::Version, game_path: &path::Path) -> Res
 	Ok(())
 }
 
-pub fn generate_game_args(meta: &api::meta::Version, game_path: &path::Path, account: &auth::Account) -> Vec<ffi::OsString> {
-	let mut args = Vec::new();
-	let arguments = &meta.arguments;
+/// variables of the launch arguments for `meta`, the variables of `options` override the ones of the launcher
+pub fn launch_variables(
+	meta: &api::meta::Version,
+	game_path: &path::Path,
+	natives_path: &path::Path,
+	account: &auth::Account,
+	options: &LaunchOptions,
+) -> variables::Variables {
+	#[cfg(target_family = "unix")]
+	const SEPARATOR: &str = ":";
+	#[cfg(target_family = "windows")]
+	const SEPARATOR: &str = ";";
+
 	let assets_path = path!(game_path, "assets");
-	for arg in &arguments.game {
-		match arg {
-			api::meta::Argument::String(str) => match str.as_str() {
-				"${clientid}" => args.push(osStr!(CLIENT_ID)),
-				"${auth_player_name}" => {
-					match &account {
-						auth::Account::Microsoft { name, .. } => args.push(osStr!(name)),
-						auth::Account::Offline { name, .. } => args.push(osStr!(name)),
-					};
-				}
-				"${auth_uuid}" => {
-					match &account {
-						auth::Account::Microsoft { uuid, .. } => args.push(osStr!(uuid)),
-						auth::Account::Offline { uuid, .. } => args.push(osStr!(uuid)),
-					};
-				}
-				"${user_type}" => {
-					match &account {
-						auth::Account::Microsoft { .. } => args.push(osStr!("msa")),
-						auth::Account::Offline { .. } => args.push(osStr!()),
-					};
-				}
-				"${auth_access_token}" => match &account {
-					auth::Account::Microsoft { access_token, .. } => args.push(osStr!(access_token)),
-					auth::Account::Offline { .. } => args.push(osStr!()),
-				},
-				"${game_directory}" => args.push(osStr!(game_path)),
-				"${version_name}" => args.push(osStr!(&meta.id)),
-				"${assets_root}" => args.push(osStr!(&assets_path)),
-				"${assets_index_name}" => args.push(osStr!(&meta.asset_index.id)),
-				"${version_type}" => args.push(osStr!(meta.r#type.to_string())),
-				"--xuid" | "${auth_xuid}" => {}
-				str => args.push(osStr!(str)),
-			},
-			_ => {}
+	let game_assets = {
+		let path_assets = path!(&assets_path, "indexes", format!("{}.json", meta.asset_index.id));
+		file::from_json_file::<api::assets::Assets, _>(&path_assets)
+			.ok()
+			.and_then(|assets| download::legacy_assets_directory(&assets, &meta.asset_index.id, game_path))
+			.unwrap_or_else(|| assets_path.clone())
+	};
+	let mut variables = variables::Variables::new();
+	match account {
+		auth::Account::Microsoft {
+			name, uuid, access_token, ..
+		} => {
+			variables.set("auth_player_name", name);
+			variables.set("auth_uuid", uuid);
+			variables.set("auth_access_token", access_token);
+			variables.set("auth_session", format!("token:{access_token}:{uuid}"));
+			variables.set("user_type", "msa");
 		}
+		auth::Account::Offline { name, uuid, .. } => {
+			variables.set("auth_player_name", name);
+			variables.set("auth_uuid", uuid);
+			variables.set("auth_access_token", "");
+			variables.set("auth_session", "-");
+			variables.set("user_type", "");
+		}
+	}
+	variables.set("auth_xuid", "");
+	variables.set("clientid", CLIENT_ID);
+	variables.set("user_properties", "{}");
+	variables.set("game_directory", game_path);
+	variables.set("version_name", &meta.id);
+	variables.set("version_type", meta.r#type.to_string());
+	variables.set("assets_root", &assets_path);
+	variables.set("game_assets", &game_assets);
+	variables.set("assets_index_name", &meta.asset_index.id);
+	variables.set("launcher_name", LAUNCHER_NAME);
+	variables.set("launcher_version", LAUNCHER_VERSION);
+	variables.set("natives_directory", natives_path);
+	variables.set("library_directory", path!(game_path, "libraries"));
+	variables.set("classpath_separator", SEPARATOR);
+	variables.set("classpath", get_class_paths(meta, game_path));
+	if options.has_custom_resolution() {
+		variables.set("resolution_width", options.width.unwrap_or(DEFAULT_WIDTH).to_string());
+		variables.set("resolution_height", options.height.unwrap_or(DEFAULT_HEIGHT).to_string());
+	}
+	match &options.quick_play {
+		Some(QuickPlay::Singleplayer(world)) => variables.set("quick_play_singleplayer", world),
+		Some(QuickPlay::Multiplayer(address)) => variables.set("quick_play_multiplayer", address),
+		Some(QuickPlay::Realms(realm)) => variables.set("quick_play_realms", realm),
+		None => {}
+	}
+	variables.extend(&options.variables);
+	variables
+}
+
+pub fn generate_game_args(meta: &api::meta::Version, variables: &variables::Variables, options: &LaunchOptions) -> Vec<ffi::OsString> {
+	let mut args = Vec::new();
+	let arguments = meta.arguments();
+	let mut skip = false;
+	for arg in rules::expand_arguments(&arguments.game, &options.features()) {
+		// the xuid is never known
+		if std::mem::take(&mut skip) && arg == "${auth_xuid}" {
+			continue;
+		}
+		if arg == "--xuid" {
+			skip = true;
+			continue;
+		}
+		args.push(variables.substitute(arg));
+	}
+	if !args.iter().any(|arg| arg == "--userProperties") {
+		args.push(osStr!("--userProperties"));
+		args.push(osStr!("{}"));
+	}
+	// versions without conditional arguments only read the resolution from the command line
+	if options.has_custom_resolution() && !args.iter().any(|arg| arg == "--width") {
+		args.push(osStr!("--width"));
+		args.push(variables.substitute("${resolution_width}"));
+		args.push(osStr!("--height"));
+		args.push(variables.substitute("${resolution_height}"));
+	}
+	if options.fullscreen && !args.iter().any(|arg| arg == "--fullscreen") {
+		args.push(osStr!("--fullscreen"));
+	}
+	match &options.quick_play {
+		Some(quick_play) if args.iter().any(|arg| arg == quick_play.argument()) => {}
+		// versions before quick play only join servers
+		Some(QuickPlay::Multiplayer(address)) => {
+			let (host, port) = split_address(address);
+			args.push(osStr!("--server"));
+			args.push(osStr!(host));
+			args.push(osStr!("--port"));
+			args.push(osStr!(port.to_string()));
+		}
+		Some(quick_play) => log::warn!("{} is not supported by {}, starting in the menu", quick_play.argument(), meta.id),
+		None => {}
 	}
-	args.push(osStr!("--userProperties"));
-	args.push(osStr!("{}"));
 	args
 }
 
-pub fn generate_jvm_args(meta: &api::meta::Version, game_path: &path::Path) -> Vec<ffi::OsString> {
+pub fn generate_jvm_args(meta: &api::meta::Version, variables: &variables::Variables, options: &LaunchOptions) -> Vec<ffi::OsString> {
 	let mut args = Vec::new();
-	let arguments = &meta.arguments;
-	let natives_dir = path!(game_path, "bin");
-	let class_paths = get_class_paths(meta, game_path);
-	for arg in &arguments.jvm {
-		match arg {
-			api::meta::Argument::String(str) => match str.as_str() {
-				"-Dminecraft.launcher.version=${launcher_version}" => {
-					let value = osStr!("-Dminecraft.launcher.version=", LAUNCHER_VERSION);
-					args.push(value);
-				}
-				"-Dminecraft.launcher.brand=${launcher_name}" => {
-					let value = osStr!("-Dminecraft.launcher.brand=", LAUNCHER_NAME);
-					args.push(value);
-				}
-				"-Djava.library.path=${natives_directory}" => {
-					let value = osStr!("-Djava.library.path=", &natives_dir);
-					args.push(value);
-				}
-				"-Djna.tmpdir=${natives_directory}" => {
-					let value = osStr!("-Djna.tmpdir=", &natives_dir);
-					args.push(value);
-				}
-				"-Dorg.lwjgl.system.SharedLibraryExtractPath=${natives_directory}" => {
-					let value = osStr!("-Dorg.lwjgl.system.SharedLibraryExtractPath=", &natives_dir);
-					args.push(value);
-				}
-				"-Dio.netty.native.workdir=${natives_directory}" => {
-					let value = osStr!("-Dio.netty.native.workdir=", &natives_dir);
-					args.push(value);
-				}
-				"${classpath}" => args.push(class_paths.clone()),
-				_ => args.push(ffi::OsString::from(str)),
-			},
-			_ => {}
+	if let Some(memory) = options.min_memory {
+		args.push(osStr!(format!("-Xms{memory}M")));
+	}
+	if let Some(memory) = options.max_memory {
+		args.push(osStr!(format!("-Xmx{memory}M")));
+	}
+	args.extend(options.jvm_args.iter().map(|arg| variables.substitute(arg)));
+	let arguments = meta.arguments();
+	for arg in rules::expand_arguments(&arguments.jvm, &options.features()) {
+		args.push(variables.substitute(arg));
+	}
+	if let (Some(logging), Some(assets_root)) = (
+		meta.logging.as_ref().and_then(|logging| logging.client.as_ref()),
+		variables.get("assets_root"),
+	) {
+		let path = download::logging_config_path(path::Path::new(assets_root), &logging.file.id);
+		if path.is_file() {
+			let mut variables = variables.clone();
+			variables.set("path", path);
+			args.push(variables.substitute(&logging.argument));
+		} else {
+			log::warn!("Logging configuration {path:?} is missing, the version has to be downloaded again");
 		}
 	}
+	if log4j_vulnerable(meta) && !args.iter().any(|arg| arg == LOG4J_NO_LOOKUPS) {
+		args.push(osStr!(LOG4J_NO_LOOKUPS));
+	}
 	args
 }
 
+/// the version ships a log4j-core older than 2.17, which is affected by log4shell
+fn log4j_vulnerable(meta: &api::meta::Version) -> bool {
+	meta.libraries.iter().filter_map(|library| library.coordinate().ok()).any(|coordinate| {
+		if coordinate.group != "org.apache.logging.log4j" || coordinate.artifact != "log4j-core" {
+			return false;
+		}
+		let mut numbers = coordinate
+			.version
+			.split(['.', '-'])
+			.map(|number| number.parse::<u32>().unwrap_or_default());
+		let major = numbers.next().unwrap_or_default();
+		let minor = numbers.next().unwrap_or_default();
+		major == 2 && minor < 17
+	})
+}
+
 pub fn get_class_paths(meta: &api::meta::Version, game_path: &path::Path) -> ffi::OsString {
-	#[cfg(target_os = "linux")]
-	const OS_NAME: Option<api::meta::OsName> = Some(api::meta::OsName::Linux);
-	#[cfg(target_os = "windows")]
-	const OS_NAME: Option<api::meta::OsName> = Some(api::meta::OsName::Windows);
-	#[cfg(target_arch = "x86")]
-	const ARCH: Option<api::meta::Arch> = Some(api::meta::Arch::X86);
-	#[cfg(not(target_arch = "x86"))]
-	const ARCH: Option<api::meta::Arch> = None;
 	#[cfg(target_family = "unix")]
 	const SEPARATOR: &'static str = ":";
 	#[cfg(target_family = "windows")]
@@ -222,54 +607,30 @@ pub fn get_class_paths(meta: &api::meta::Version, game_path: &path::Path) -> ffi
 		let path = path!(game_path, "versions", &meta.id, format!("{}.jar", meta.id));
 		list.push(path);
 	}
-	'l: for lib in &meta.libraries {
-		match lib {
-			api::meta::Library {
-				downloads: Some(api::meta::LibraryDownload {
-					artifact: Some(artifact), ..
-				}),
-				rules,
-				..
-			} => {
-				if let Some(rules) = rules {
-					for api::meta::Rule { os, action } in rules {
-						match (os, action) {
-							(Some(api::meta::Os { name: OS_NAME, arch: ARCH }), api::meta::Action::Allow) => {}
-							(Some(api::meta::Os { name: OS_NAME, arch: ARCH }), api::meta::Action::Disallow) => continue 'l,
-							(None, api::meta::Action::Allow) => {}
-							(None, api::meta::Action::Disallow) => continue 'l,
-							(_, api::meta::Action::Disallow) => {}
-							(_, api::meta::Action::Allow) => continue 'l,
-						}
-					}
-				}
+	for lib in &meta.libraries {
+		if lib
+			.rules
+			.as_ref()
+			.is_some_and(|rules| !rules::is_allowed(rules, &rules::Features::default()))
+		{
+			continue;
+		}
+		match &lib.downloads {
+			Some(api::meta::LibraryDownload {
+				artifact: Some(artifact), ..
+			}) => {
 				list.push(SEPARATOR);
-				let path = path!(game_path, "libraries", &artifact.path);
-				list.push(path);
+				list.push(path!(game_path, "libraries", &artifact.path));
 			}
-			lib => {
-				let mut original_name: &str = &lib.name;
-				let mut name = "";
-				let mut version = "";
-				let mut path = path!();
-
-				if let Some(i) = original_name.find(":") {
-					let mut paths = &original_name[..i];
-					original_name = &original_name[(i + 1)..];
-					while let Some(i) = paths.find(".") {
-						path = path!(path, &paths[..i]);
-						paths = &paths[(i + 1)..];
-					}
-					path = path!(path, &paths);
-				}
-				if let Some(i) = original_name.find(":") {
-					name = &original_name[..i];
-					version = &original_name[(i + 1)..];
+			// only natives, which are extracted instead
+			Some(_) => {}
+			None => match lib.coordinate() {
+				Ok(coordinate) => {
+					list.push(SEPARATOR);
+					list.push(path!(game_path, "libraries", coordinate.path()));
 				}
-				list.push(SEPARATOR);
-				let path = path!(game_path, "libraries", &path, name, version, format!("{name}-{version}.jar"));
-				list.push(path);
-			}
+				Err(e) => log::warn!("Skipping library {}: {e:?}", lib.name),
+			},
 		}
 	}
 	list
@@ -306,6 +667,227 @@ pub fn list_versions(game_path: &path::Path) -> Result<Vec<String>, error::Error
 		.collect()
 }
 
-pub fn list_all_versions() -> Result<api::manifest::Manifest, error::Error> {
-	api::get_from_url(api::manifest::Manifest::DEFAULT_URL)
+pub fn list_all_versions(network: &net::Network) -> Result<api::manifest::Manifest, error::Error> {
+	api::get_from_url(network, net::Resource::Manifest, api::manifest::Manifest::DEFAULT_URL)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn child_libraries_replace_parent_libraries() {
+		let parent = serde_json::json!({
+			"id": "1.20.1",
+			"mainClass": "net.minecraft.client.main.Main",
+			"libraries": [
+				{ "name": "org.ow2.asm:asm:9.1" },
+				{ "name": "org.lwjgl:lwjgl:3.3.1" },
+				{ "name": "org.lwjgl:lwjgl:3.3.1:natives-linux" }
+			]
+		});
+		let child = serde_json::json!({
+			"id": "loader-1.20.1",
+			"mainClass": "net.fabricmc.loader.impl.launch.knot.KnotClient",
+			"libraries": [{ "name": "org.ow2.asm:asm:9.6" }, { "name": "net.fabricmc:fabric-loader:0.15.0" }]
+		});
+		let (serde_json::Value::Object(parent), serde_json::Value::Object(child)) = (parent, child) else {
+			unreachable!();
+		};
+		let version = merge_version(parent, child);
+		assert_eq!(version["id"], "loader-1.20.1");
+		assert_eq!(version["mainClass"], "net.fabricmc.loader.impl.launch.knot.KnotClient");
+		let names: Vec<&str> = version["libraries"]
+			.as_array()
+			.unwrap()
+			.iter()
+			.map(|library| library["name"].as_str().unwrap())
+			.collect();
+		assert_eq!(
+			names,
+			[
+				"org.ow2.asm:asm:9.6",
+				"net.fabricmc:fabric-loader:0.15.0",
+				"org.lwjgl:lwjgl:3.3.1",
+				"org.lwjgl:lwjgl:3.3.1:natives-linux"
+			]
+		);
+	}
+
+	#[cfg(target_family = "unix")]
+	#[test]
+	fn clean_natives_keeps_live_and_fresh_directories() {
+		let game_path = file::temp_dir("clean-natives");
+		let natives = |name: &str, owner: Option<u32>, age: time::Duration| {
+			let path = path!(&game_path, "natives", name);
+			fs::create_dir_all(&path).unwrap();
+			if let Some(pid) = owner {
+				own_natives(&path, pid).unwrap();
+			}
+			let modified = time::SystemTime::now() - age;
+			fs::File::open(&path).unwrap().set_modified(modified).unwrap();
+			path
+		};
+		let day = STALE_NATIVES + time::Duration::from_secs(60);
+		let fresh = natives("fresh", None, time::Duration::ZERO);
+		let old = natives("old", None, day);
+		let running = natives("running", Some(process::id()), day);
+		let exited = natives("exited", Some(u32::MAX), time::Duration::ZERO);
+		let exported = natives("export-1.20.1", None, day);
+
+		clean_natives(&game_path);
+		assert!(fresh.exists());
+		assert!(!old.exists());
+		assert!(running.exists());
+		assert!(!exited.exists());
+		assert!(exported.exists());
+		fs::remove_dir_all(game_path).unwrap();
+	}
+
+	#[test]
+	fn extract_natives_honors_exclude() {
+		use std::io::Write;
+
+		let game_path = file::temp_dir("extract-natives");
+		let mut zip = zip::ZipWriter::new(file::create_or_open_file(&path!(&game_path, "libraries", "natives.jar")).unwrap());
+		for name in ["liblwjgl.so", "lwjgl.dll", "META-INF/libsigned.so", "README.txt"] {
+			zip.start_file(name, zip::write::SimpleFileOptions::default()).unwrap();
+			zip.write_all(name.as_bytes()).unwrap();
+		}
+		zip.finish().unwrap();
+		let natives = serde_json::json!({ "url": "", "path": "natives.jar", "sha1": "" });
+		let meta = api::meta::test_version(serde_json::json!({
+			"libraries": [{
+				"name": "org.lwjgl.lwjgl:lwjgl-platform:2.9.4-nightly-20150209",
+				"downloads": { "classifiers": { "natives-linux": natives, "natives-osx": natives, "natives-windows": natives } },
+				"extract": { "exclude": ["META-INF/"] }
+			}]
+		}));
+
+		let natives_path = path!(&game_path, "natives", "1.12.2-0");
+		extract_natives(&meta, &game_path, &natives_path).unwrap();
+		let mut names: Vec<_> = fs::read_dir(&natives_path).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+		names.sort();
+		assert_eq!(names, ["liblwjgl.so", "lwjgl.dll"]);
+		fs::remove_dir_all(game_path).unwrap();
+	}
+
+	#[test]
+	fn launch_options_arguments() {
+		let meta = api::meta::test_version(serde_json::json!({
+			"minecraftArguments": "--username ${auth_player_name}"
+		}));
+		let options = LaunchOptions {
+			min_memory: Some(512),
+			max_memory: Some(2048),
+			jvm_args: vec!["-Dgame=${version_name}".to_string()],
+			width: Some(1280),
+			fullscreen: true,
+			..Default::default()
+		};
+		let mut variables = variables::Variables::new();
+		variables.set("version_name", "1.12.2");
+		variables.set("auth_player_name", "Steve");
+		variables.set("resolution_width", "1280");
+		variables.set("resolution_height", DEFAULT_HEIGHT.to_string());
+
+		let jvm_args = generate_jvm_args(&meta, &variables, &options);
+		assert_eq!(jvm_args[..3], ["-Xms512M", "-Xmx2048M", "-Dgame=1.12.2"]);
+		let game_args = generate_game_args(&meta, &variables, &options);
+		assert_eq!(
+			game_args,
+			[
+				"--username",
+				"Steve",
+				"--userProperties",
+				"{}",
+				"--width",
+				"1280",
+				"--height",
+				"480",
+				"--fullscreen"
+			]
+		);
+	}
+
+	#[test]
+	fn server_addresses() {
+		assert_eq!(split_address("mc.example.com"), ("mc.example.com", 25565));
+		assert_eq!(split_address("mc.example.com:25566"), ("mc.example.com", 25566));
+		assert_eq!(split_address("[::1]:25566"), ("::1", 25566));
+		assert_eq!(split_address("::1"), ("::1", 25565));
+	}
+
+	#[test]
+	fn quick_play_arguments() {
+		let modern = api::meta::test_version(serde_json::json!({
+			"id": "1.20.1",
+			"arguments": {
+				"game": [
+					"--username",
+					"${auth_player_name}",
+					{
+						"rules": [{ "action": "allow", "features": { "is_quick_play_multiplayer": true } }],
+						"value": ["--quickPlayMultiplayer", "${quick_play_multiplayer}"]
+					}
+				],
+				"jvm": []
+			}
+		}));
+		let legacy = api::meta::test_version(serde_json::json!({
+			"minecraftArguments": "--username ${auth_player_name}"
+		}));
+		let multiplayer = LaunchOptions {
+			quick_play: Some(QuickPlay::Multiplayer("mc.example.com:25566".to_string())),
+			..Default::default()
+		};
+		let mut variables = variables::Variables::new();
+		variables.set("auth_player_name", "Steve");
+		variables.set("quick_play_multiplayer", "mc.example.com:25566");
+
+		assert_eq!(
+			generate_game_args(&modern, &variables, &multiplayer),
+			[
+				"--username",
+				"Steve",
+				"--quickPlayMultiplayer",
+				"mc.example.com:25566",
+				"--userProperties",
+				"{}"
+			]
+		);
+		assert_eq!(
+			generate_game_args(&legacy, &variables, &multiplayer),
+			[
+				"--username",
+				"Steve",
+				"--userProperties",
+				"{}",
+				"--server",
+				"mc.example.com",
+				"--port",
+				"25566"
+			]
+		);
+		let singleplayer = LaunchOptions {
+			quick_play: Some(QuickPlay::Singleplayer("New World".to_string())),
+			..Default::default()
+		};
+		assert_eq!(
+			generate_game_args(&legacy, &variables, &singleplayer),
+			["--username", "Steve", "--userProperties", "{}"]
+		);
+	}
+
+	#[test]
+	fn log4j_versions() {
+		let mut meta = api::meta::test_version(serde_json::json!({
+			"libraries": [{ "name": "org.apache.logging.log4j:log4j-core:2.8.1" }]
+		}));
+		assert!(log4j_vulnerable(&meta));
+		meta.libraries[0].name = "org.apache.logging.log4j:log4j-core:2.0-beta9".to_string();
+		assert!(log4j_vulnerable(&meta));
+		meta.libraries[0].name = "org.apache.logging.log4j:log4j-core:2.17.0".to_string();
+		assert!(!log4j_vulnerable(&meta));
+	}
 }
@@ -1,6 +1,16 @@
 pub mod api;
 pub mod auth;
+pub mod crash;
 pub mod download;
 pub mod error;
 mod file;
+pub mod game;
+pub mod gamelog;
+pub mod hook;
 pub mod launch;
+pub mod maven;
+pub mod net;
+pub mod plan;
+pub mod rules;
+pub mod server;
+pub mod variables;
@@ -0,0 +1,126 @@
+use crate::*;
+
+use std::fmt;
+use std::path;
+use std::str;
+
+/// maven coordinate `group:artifact:version[:classifier][@extension]`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Coordinate {
+	pub group: String,
+	pub artifact: String,
+	pub version: String,
+	pub classifier: Option<String>,
+	/// `jar` when not specified
+	pub extension: String,
+}
+
+impl Coordinate {
+	/// `group:artifact[:classifier]`, which identifies a library across its versions
+	pub fn key(&self) -> String {
+		match &self.classifier {
+			Some(classifier) => format!("{}:{}:{classifier}", self.group, self.artifact),
+			None => format!("{}:{}", self.group, self.artifact),
+		}
+	}
+
+	/// `artifact-version[-classifier].extension`
+	pub fn file_name(&self) -> String {
+		match &self.classifier {
+			Some(classifier) => format!("{}-{}-{classifier}.{}", self.artifact, self.version, self.extension),
+			None => format!("{}-{}.{}", self.artifact, self.version, self.extension),
+		}
+	}
+
+	/// path of the file relative to the repository root, joined with `/`
+	pub fn url_path(&self) -> String {
+		format!("{}/{}/{}/{}", self.group.replace('.', "/"), self.artifact, self.version, self.file_name())
+	}
+
+	/// url of the file in the repository at `base`
+	pub fn url(&self, base: &str) -> String {
+		format!("{}/{}", base.trim_end_matches('/'), self.url_path())
+	}
+
+	/// path of the file relative to the repository root
+	pub fn path(&self) -> path::PathBuf {
+		let mut path: path::PathBuf = self.group.split('.').collect();
+		path.push(&self.artifact);
+		path.push(&self.version);
+		path.push(self.file_name());
+		path
+	}
+}
+
+impl str::FromStr for Coordinate {
+	type Err = error::Error;
+
+	fn from_str(coordinate: &str) -> Result<Self, Self::Err> {
+		let (name, extension) = match coordinate.split_once('@') {
+			Some((name, extension)) => (name, extension),
+			None => (coordinate, "jar"),
+		};
+		let parts: Vec<&str> = name.split(':').collect();
+		if parts.iter().any(|part| part.is_empty()) || extension.is_empty() {
+			return Err(error::Error::InvalidCoordinate(coordinate.to_string()));
+		}
+		let (group, artifact, version, classifier) = match parts.as_slice() {
+			[group, artifact, version] => (group, artifact, version, None),
+			[group, artifact, version, classifier] => (group, artifact, version, Some(classifier.to_string())),
+			_ => return Err(error::Error::InvalidCoordinate(coordinate.to_string())),
+		};
+		Ok(Self {
+			group: group.to_string(),
+			artifact: artifact.to_string(),
+			version: version.to_string(),
+			classifier,
+			extension: extension.to_string(),
+		})
+	}
+}
+
+impl fmt::Display for Coordinate {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}:{}:{}", self.group, self.artifact, self.version)?;
+		if let Some(classifier) = &self.classifier {
+			write!(f, ":{classifier}")?;
+		}
+		if self.extension != "jar" {
+			write!(f, "@{}", self.extension)?;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn coordinate_forms() {
+		let plain: Coordinate = "com.mojang:brigadier:1.0.18".parse().unwrap();
+		assert_eq!(plain.url_path(), "com/mojang/brigadier/1.0.18/brigadier-1.0.18.jar");
+		assert_eq!(plain.path(), path!("com", "mojang", "brigadier", "1.0.18", "brigadier-1.0.18.jar"));
+		assert_eq!(plain.key(), "com.mojang:brigadier");
+
+		let natives: Coordinate = "org.lwjgl:lwjgl:3.3.1:natives-linux".parse().unwrap();
+		assert_eq!(natives.url_path(), "org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1-natives-linux.jar");
+		assert_eq!(natives.key(), "org.lwjgl:lwjgl:natives-linux");
+
+		let zip: Coordinate = "net.minecraftforge:forge:1.20.1-47.2.0:universal@zip".parse().unwrap();
+		assert_eq!(
+			zip.url("https://maven.minecraftforge.net/"),
+			"https://maven.minecraftforge.net/net/minecraftforge/forge/1.20.1-47.2.0/forge-1.20.1-47.2.0-universal.zip"
+		);
+
+		for name in [
+			"com.mojang:brigadier:1.0.18",
+			"org.lwjgl:lwjgl:3.3.1:natives-linux",
+			"de.oceanlabs.mcp:mcp_config:1.20.1@zip",
+		] {
+			assert_eq!(name.parse::<Coordinate>().unwrap().to_string(), name);
+		}
+		assert!("com.mojang:brigadier".parse::<Coordinate>().is_err());
+		assert!("com.mojang::1.0".parse::<Coordinate>().is_err());
+	}
+}
@@ -0,0 +1,305 @@
+use crate::*;
+
+use std::{thread, time};
+
+/// hosts used by mojang, only urls starting with one of them are sent to a mirror
+const MOJANG_ORIGINS: &[&str] = &[
+	"https://launchermeta.mojang.com",
+	"https://piston-meta.mojang.com",
+	"https://piston-data.mojang.com",
+	"https://launcher.mojang.com",
+	"https://libraries.minecraft.net",
+	"https://resources.download.minecraft.net",
+];
+
+pub const BMCLAPI_URL: &str = "https://bmclapi2.bangbang93.com";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Resource {
+	/// version manifest
+	Manifest,
+	/// version json, asset index and client/server jars
+	Meta,
+	Library,
+	Asset,
+	/// java runtime list, manifests and files
+	Java,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+	/// attempts made against each host before falling back to the next one
+	pub attempts: u32,
+	pub initial_delay: time::Duration,
+	pub max_delay: time::Duration,
+}
+
+impl Default for RetryPolicy {
+	#[inline(always)]
+	fn default() -> Self {
+		Self {
+			attempts: 4,
+			initial_delay: time::Duration::from_millis(500),
+			max_delay: time::Duration::from_secs(30),
+		}
+	}
+}
+
+/// ordered fallback bases for each resource, tried after the original url fails
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Mirrors {
+	pub manifest: Vec<String>,
+	pub meta: Vec<String>,
+	pub libraries: Vec<String>,
+	pub assets: Vec<String>,
+	pub java: Vec<String>,
+}
+
+impl Mirrors {
+	pub fn bmclapi() -> Self {
+		Self {
+			manifest: vec![BMCLAPI_URL.to_string()],
+			meta: vec![BMCLAPI_URL.to_string()],
+			libraries: vec![format!("{BMCLAPI_URL}/maven")],
+			assets: vec![format!("{BMCLAPI_URL}/assets")],
+			java: vec![BMCLAPI_URL.to_string()],
+		}
+	}
+	/// the same base for every resource
+	pub fn all(base: &str) -> Self {
+		Self {
+			manifest: vec![base.to_string()],
+			meta: vec![base.to_string()],
+			libraries: vec![base.to_string()],
+			assets: vec![base.to_string()],
+			java: vec![base.to_string()],
+		}
+	}
+	pub fn append(&mut self, mut other: Self) {
+		self.manifest.append(&mut other.manifest);
+		self.meta.append(&mut other.meta);
+		self.libraries.append(&mut other.libraries);
+		self.assets.append(&mut other.assets);
+		self.java.append(&mut other.java);
+	}
+	pub fn get(&self, resource: Resource) -> &[String] {
+		match resource {
+			Resource::Manifest => &self.manifest,
+			Resource::Meta => &self.meta,
+			Resource::Library => &self.libraries,
+			Resource::Asset => &self.assets,
+			Resource::Java => &self.java,
+		}
+	}
+}
+
+/// bases replacing the mojang hosts, `None` keeps the original url
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Endpoints {
+	pub manifest: Option<String>,
+	pub meta: Option<String>,
+	pub libraries: Option<String>,
+	pub assets: Option<String>,
+	pub java: Option<String>,
+}
+
+impl Endpoints {
+	pub fn bmclapi() -> Self {
+		Self {
+			manifest: Some(BMCLAPI_URL.to_string()),
+			meta: Some(BMCLAPI_URL.to_string()),
+			libraries: Some(format!("{BMCLAPI_URL}/maven")),
+			assets: Some(format!("{BMCLAPI_URL}/assets")),
+			java: Some(BMCLAPI_URL.to_string()),
+		}
+	}
+	/// the same base for every resource
+	pub fn all(base: &str) -> Self {
+		Self {
+			manifest: Some(base.to_string()),
+			meta: Some(base.to_string()),
+			libraries: Some(base.to_string()),
+			assets: Some(base.to_string()),
+			java: Some(base.to_string()),
+		}
+	}
+	pub fn get(&self, resource: Resource) -> Option<&str> {
+		match resource {
+			Resource::Manifest => self.manifest.as_deref(),
+			Resource::Meta => self.meta.as_deref(),
+			Resource::Library => self.libraries.as_deref(),
+			Resource::Asset => self.assets.as_deref(),
+			Resource::Java => self.java.as_deref(),
+		}
+	}
+	/// `url` with its mojang host replaced by the endpoint configured for `resource`
+	pub fn url(&self, resource: Resource, url: &str) -> String {
+		self.get(resource).and_then(|base| rebase(url, base)).unwrap_or_else(|| url.to_string())
+	}
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Network {
+	pub retry: RetryPolicy,
+	pub endpoints: Endpoints,
+	pub mirrors: Mirrors,
+}
+
+enum Retry {
+	Never,
+	Backoff,
+	After(time::Duration),
+}
+
+fn classify(error: &error::Error) -> Retry {
+	match error {
+		error::Error::Network(ureq::Error::Status(429 | 503, response)) => {
+			match response.header("Retry-After").and_then(|after| after.trim().parse().ok()) {
+				Some(seconds) => Retry::After(time::Duration::from_secs(seconds)),
+				None => Retry::Backoff,
+			}
+		}
+		error::Error::Network(ureq::Error::Status(408 | 500..=599, _)) => Retry::Backoff,
+		error::Error::Network(ureq::Error::Status(..)) => Retry::Never,
+		error::Error::Network(ureq::Error::Transport(_)) | error::Error::Io(_) | error::Error::Integrity(_) | error::Error::Lzma(_) => Retry::Backoff,
+		_ => Retry::Never,
+	}
+}
+
+/// replaces the mojang origin of `url` with `base`
+pub fn rebase(url: &str, base: &str) -> Option<String> {
+	let origin = MOJANG_ORIGINS.iter().find(|origin| url.starts_with(*origin))?;
+	Some(format!("{}{}", base.trim_end_matches('/'), &url[origin.len()..]))
+}
+
+fn candidates(network: &Network, resource: Resource, url: &str) -> Vec<String> {
+	let mut list = vec![network.endpoints.url(resource, url)];
+	list.extend(network.mirrors.get(resource).iter().filter_map(|base| rebase(url, base)));
+	list
+}
+
+/// runs `fetch` against `url` and then its mirrors, retrying transient failures with exponential backoff
+pub(crate) fn with_retry<T, F>(network: &Network, resource: Resource, url: &str, mut fetch: F) -> Result<T, error::Error>
+where
+	F: FnMut(&str) -> Result<T, error::Error>,
+{
+	let mut last_error = None;
+	for url in candidates(network, resource, url) {
+		let attempts = network.retry.attempts.max(1);
+		let mut delay = network.retry.initial_delay;
+		for attempt in 1..=attempts {
+			let error = match fetch(&url) {
+				Ok(value) => return Ok(value),
+				Err(error) => error,
+			};
+			let wait = match classify(&error) {
+				Retry::Never => None,
+				_ if attempt == attempts => None,
+				Retry::Backoff => Some(delay),
+				// a server asking for more than the backoff ever waits must not hold a worker for that long
+				Retry::After(after) => Some(after.min(network.retry.max_delay)),
+			};
+			log::warn!("Failed to fetch {url} (attempt {attempt}): {error:?}");
+			last_error = Some(error);
+			let Some(wait) = wait else {
+				break;
+			};
+			thread::sleep(wait);
+			delay = (delay * 2).min(network.retry.max_delay);
+		}
+	}
+	Err(last_error.expect("at least one attempt"))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn rebase_mojang_urls() {
+		let url = "https://libraries.minecraft.net/com/mojang/brigadier/1.0.18/brigadier-1.0.18.jar";
+		assert_eq!(
+			rebase(url, "https://bmclapi2.bangbang93.com/maven/").as_deref(),
+			Some("https://bmclapi2.bangbang93.com/maven/com/mojang/brigadier/1.0.18/brigadier-1.0.18.jar")
+		);
+		assert_eq!(rebase("https://maven.fabricmc.net/net/fabricmc/sponge-mixin.jar", BMCLAPI_URL), None);
+	}
+
+	#[test]
+	fn endpoints_rewrite_only_their_resource() {
+		let endpoints = Endpoints {
+			assets: Some("http://localhost:8080/assets".to_string()),
+			..Default::default()
+		};
+		let url = "https://resources.download.minecraft.net/ab/abcdef";
+		assert_eq!(endpoints.url(Resource::Asset, url), "http://localhost:8080/assets/ab/abcdef");
+		assert_eq!(endpoints.url(Resource::Meta, url), url);
+	}
+
+	fn status(response: &str) -> error::Error {
+		let response: ureq::Response = response.parse().unwrap();
+		error::Error::Network(ureq::Error::Status(response.status(), response))
+	}
+
+	#[test]
+	fn retry_each_host_in_order() {
+		let network = Network {
+			retry: RetryPolicy {
+				attempts: 2,
+				initial_delay: time::Duration::ZERO,
+				max_delay: time::Duration::ZERO,
+			},
+			mirrors: Mirrors {
+				libraries: vec!["http://first.example".to_string(), "http://second.example".to_string()],
+				..Default::default()
+			},
+			..Default::default()
+		};
+		let url = "https://libraries.minecraft.net/a.jar";
+		let run = |response: &str, succeed_after: usize| {
+			let mut tried = Vec::new();
+			let result = with_retry(&network, Resource::Library, url, |url| {
+				tried.push(url.to_string());
+				if tried.len() > succeed_after {
+					Ok(())
+				} else {
+					Err(status(response))
+				}
+			});
+			(result.is_ok(), tried)
+		};
+
+		// transient failures are retried on every host before giving up
+		assert_eq!(
+			run("HTTP/1.1 503 Service Unavailable\r\n\r\n", usize::MAX),
+			(
+				false,
+				vec![
+					url.to_string(),
+					url.to_string(),
+					"http://first.example/a.jar".to_string(),
+					"http://first.example/a.jar".to_string(),
+					"http://second.example/a.jar".to_string(),
+					"http://second.example/a.jar".to_string(),
+				]
+			)
+		);
+		// a missing file is not retried but still looked up on the mirrors
+		assert_eq!(
+			run("HTTP/1.1 404 Not Found\r\n\r\n", usize::MAX),
+			(
+				false,
+				vec![
+					url.to_string(),
+					"http://first.example/a.jar".to_string(),
+					"http://second.example/a.jar".to_string()
+				]
+			)
+		);
+		// the host asking to wait is tried again before the mirrors
+		assert_eq!(
+			run("HTTP/1.1 429 Too Many Requests\r\nRetry-After: 0\r\n\r\n", 1),
+			(true, vec![url.to_string(), url.to_string()])
+		);
+	}
+}
@@ -0,0 +1,183 @@
+use std::collections;
+use std::ffi;
+use std::path;
+use std::process;
+
+/// replaces the access token in redacted plans
+pub const REDACTED_ACCESS_TOKEN: &str = "${auth_access_token}";
+
+/// arguments kept as `OsString` to run them untouched, written as text when the plan is serialized
+mod lossy {
+	use std::ffi;
+
+	pub fn serialize<S: serde::Serializer>(args: &[ffi::OsString], serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.collect_seq(args.iter().map(|arg| arg.to_string_lossy()))
+	}
+
+	pub fn deserialize<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<ffi::OsString>, D::Error> {
+		let args: Vec<String> = serde::Deserialize::deserialize(deserializer)?;
+		Ok(args.into_iter().map(ffi::OsString::from).collect())
+	}
+
+	pub fn serialize_path<S: serde::Serializer>(path: &std::path::Path, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.serialize_str(&path.to_string_lossy())
+	}
+}
+
+/// everything a launch runs, computed by `launch::plan_launch` without spawning anything
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct LaunchPlan {
+	pub version: String,
+	/// commands the game is run through, see `launch::LaunchOptions::wrappers`
+	pub wrappers: Vec<Vec<String>>,
+	#[serde(serialize_with = "lossy::serialize_path")]
+	pub java: path::PathBuf,
+	#[serde(serialize_with = "lossy::serialize_path")]
+	pub working_directory: path::PathBuf,
+	/// where the native libraries are extracted
+	#[serde(serialize_with = "lossy::serialize_path")]
+	pub natives_directory: path::PathBuf,
+	#[serde(with = "lossy")]
+	pub jvm_args: Vec<ffi::OsString>,
+	pub main_class: String,
+	#[serde(with = "lossy")]
+	pub game_args: Vec<ffi::OsString>,
+	/// environment of the game on top of the one of the launcher, `None` removes the variable
+	pub environment: collections::BTreeMap<String, Option<String>>,
+	/// token of the account, left out of the serialized plan
+	#[serde(skip)]
+	pub(crate) access_token: String,
+}
+
+/// `arg` quoted for `sh` when it contains anything else than `[A-Za-z0-9_@%+=:,./-]`
+fn shell_quote(arg: &str) -> String {
+	let plain = !arg.is_empty() && arg.chars().all(|c| c.is_ascii_alphanumeric() || "_@%+=:,./-".contains(c));
+	if plain {
+		arg.to_string()
+	} else {
+		format!("'{}'", arg.replace('\'', r"'\''"))
+	}
+}
+
+impl LaunchPlan {
+	/// wrappers, `java`, jvm arguments, main class and game arguments in the order they are run
+	pub fn program(&self) -> Vec<ffi::OsString> {
+		let mut program: Vec<ffi::OsString> = self.wrappers.iter().flatten().map(ffi::OsString::from).collect();
+		program.push(self.java.clone().into_os_string());
+		program.extend(self.jvm_args.iter().cloned());
+		program.push(self.main_class.clone().into());
+		program.extend(self.game_args.iter().cloned());
+		program
+	}
+
+	/// replaces the access token of the account in the arguments and the environment with `REDACTED_ACCESS_TOKEN`
+	pub fn redact(&mut self) {
+		let token = std::mem::take(&mut self.access_token);
+		if token.is_empty() {
+			return;
+		}
+		for arg in self.jvm_args.iter_mut().chain(self.game_args.iter_mut()) {
+			let text = arg.to_string_lossy();
+			if text.contains(&token) {
+				*arg = text.replace(&token, REDACTED_ACCESS_TOKEN).into();
+			}
+		}
+		for value in self.environment.values_mut().flatten() {
+			if value.contains(&token) {
+				*value = value.replace(&token, REDACTED_ACCESS_TOKEN);
+			}
+		}
+	}
+
+	/// command running the plan in its working directory, stdout and stderr are inherited
+	pub fn command(&self) -> process::Command {
+		let program = self.program();
+		let mut command = process::Command::new(&program[0]);
+		command.current_dir(&self.working_directory).args(&program[1..]);
+		for (name, value) in &self.environment {
+			match value {
+				Some(value) => {
+					// values may hold secrets, only the names are logged
+					log::info!("Setting environment variable {name}");
+					command.env(name, value)
+				}
+				None => {
+					log::info!("Removing environment variable {name}");
+					command.env_remove(name)
+				}
+			};
+		}
+		command
+	}
+
+	pub fn to_json(&self) -> Result<String, serde_json::Error> {
+		serde_json::to_string_pretty(self)
+	}
+
+	/// `sh` script running the plan, the natives must still be in the natives directory
+	pub fn to_shell_script(&self) -> String {
+		let mut script = String::from("#!/bin/sh\n");
+		script.push_str(&format!("# launch of {} exported by smallauncher\n", self.version));
+		script.push_str(&format!("# natives: {}\n", self.natives_directory.display()));
+		script.push_str(&format!("cd {} || exit 1\n", shell_quote(&self.working_directory.to_string_lossy())));
+		for (name, value) in &self.environment {
+			match value {
+				Some(value) => script.push_str(&format!("export {name}={}\n", shell_quote(value))),
+				None => script.push_str(&format!("unset {name}\n")),
+			}
+		}
+		let program: Vec<String> = self.program().iter().map(|arg| shell_quote(&arg.to_string_lossy())).collect();
+		script.push_str("exec ");
+		script.push_str(&program.join(" \\\n\t"));
+		script.push('\n');
+		script
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn redacted_shell_script() {
+		let mut plan = LaunchPlan {
+			version: "1.20.1".to_string(),
+			wrappers: vec![vec!["gamemoderun".to_string()]],
+			java: path::PathBuf::from("/jre/java-runtime-gamma/bin/java"),
+			working_directory: path::PathBuf::from("/home/steve/minecraft"),
+			natives_directory: path::PathBuf::from("/home/steve/minecraft/natives/1.20.1-0"),
+			jvm_args: vec!["-Xmx2048M".into(), "-Dname=it's".into()],
+			main_class: "net.minecraft.client.main.Main".to_string(),
+			game_args: vec!["--accessToken".into(), "secret".into(), "--title".into(), ffi::OsString::new()],
+			environment: [
+				("SESSION".to_string(), Some("token:secret:uuid".to_string())),
+				("DISPLAY".to_string(), None),
+			]
+			.into(),
+			access_token: "secret".to_string(),
+		};
+		plan.redact();
+		assert_eq!(
+			plan.to_shell_script(),
+			"#!/bin/sh
+# launch of 1.20.1 exported by smallauncher
+# natives: /home/steve/minecraft/natives/1.20.1-0
+cd /home/steve/minecraft || exit 1
+unset DISPLAY
+export SESSION='token:${auth_access_token}:uuid'
+exec gamemoderun \\
+\t/jre/java-runtime-gamma/bin/java \\
+\t-Xmx2048M \\
+\t'-Dname=it'\\''s' \\
+\tnet.minecraft.client.main.Main \\
+\t--accessToken \\
+\t'${auth_access_token}' \\
+\t--title \\
+\t''
+"
+		);
+		let json = plan.to_json().unwrap();
+		assert!(!json.contains("secret"));
+		assert_eq!(serde_json::from_str::<LaunchPlan>(&json).unwrap(), plan);
+	}
+}
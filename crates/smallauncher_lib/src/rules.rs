@@ -0,0 +1,164 @@
+use crate::*;
+
+use std::sync;
+
+/// values of the `features` checked by argument rules
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Features {
+	pub is_demo_user: bool,
+	pub has_custom_resolution: bool,
+	pub has_quick_plays_support: bool,
+	pub is_quick_play_singleplayer: bool,
+	pub is_quick_play_multiplayer: bool,
+	pub is_quick_play_realms: bool,
+}
+
+impl Features {
+	pub fn get(&self, name: &str) -> bool {
+		match name {
+			"is_demo_user" => self.is_demo_user,
+			"has_custom_resolution" => self.has_custom_resolution,
+			"has_quick_plays_support" => self.has_quick_plays_support,
+			"is_quick_play_singleplayer" => self.is_quick_play_singleplayer,
+			"is_quick_play_multiplayer" => self.is_quick_play_multiplayer,
+			"is_quick_play_realms" => self.is_quick_play_realms,
+			_ => false,
+		}
+	}
+}
+
+#[cfg(target_os = "linux")]
+pub const OS_NAME: api::meta::OsName = api::meta::OsName::Linux;
+#[cfg(target_os = "windows")]
+pub const OS_NAME: api::meta::OsName = api::meta::OsName::Windows;
+#[cfg(target_os = "macos")]
+pub const OS_NAME: api::meta::OsName = api::meta::OsName::Osx;
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+pub const OS_NAME: api::meta::OsName = api::meta::OsName::Unknown;
+
+#[cfg(target_arch = "x86")]
+pub const ARCH: Option<api::meta::Arch> = Some(api::meta::Arch::X86);
+#[cfg(not(target_arch = "x86"))]
+pub const ARCH: Option<api::meta::Arch> = None;
+
+/// version of the running os, in the format java reports as `os.version`
+pub fn os_version() -> &'static str {
+	static VERSION: sync::OnceLock<String> = sync::OnceLock::new();
+	VERSION.get_or_init(|| {
+		#[cfg(target_os = "linux")]
+		let version = std::fs::read_to_string("/proc/sys/kernel/osrelease").ok();
+		#[cfg(target_os = "macos")]
+		let version = std::process::Command::new("sw_vers")
+			.arg("-productVersion")
+			.output()
+			.ok()
+			.map(|output| String::from_utf8_lossy(&output.stdout).into_owned());
+		// "Microsoft Windows [Version 10.0.19045.3570]"
+		#[cfg(target_os = "windows")]
+		let version = std::process::Command::new("cmd").args(["/C", "ver"]).output().ok().and_then(|output| {
+			let output = String::from_utf8_lossy(&output.stdout).into_owned();
+			let start = output.find("Version ")? + "Version ".len();
+			let end = output[start..].find(']').map_or(output.len(), |end| start + end);
+			Some(output[start..end].to_string())
+		});
+		#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+		let version: Option<String> = None;
+		version.unwrap_or_default().trim().to_string()
+	})
+}
+
+fn os_matches(os: &api::meta::Os) -> bool {
+	if os.name.as_ref().is_some_and(|name| *name != OS_NAME) {
+		return false;
+	}
+	if os.arch.is_some() && os.arch != ARCH {
+		return false;
+	}
+	match &os.version {
+		Some(version) => regex_lite::Regex::new(version).is_ok_and(|regex| regex.is_match(os_version())),
+		None => true,
+	}
+}
+
+/// the rule applies to the current os and features
+pub fn rule_matches(rule: &api::meta::Rule, features: &Features) -> bool {
+	if rule.os.as_ref().is_some_and(|os| !os_matches(os)) {
+		return false;
+	}
+	match &rule.features {
+		Some(required) => required.iter().all(|(name, value)| features.get(name) == *value),
+		None => true,
+	}
+}
+
+/// an empty list allows everything, otherwise the action of the last matching rule wins and nothing matching disallows
+pub fn is_allowed(rules: &[api::meta::Rule], features: &Features) -> bool {
+	if rules.is_empty() {
+		return true;
+	}
+	rules
+		.iter()
+		.rev()
+		.find(|rule| rule_matches(rule, features))
+		.is_some_and(|rule| rule.action == api::meta::Action::Allow)
+}
+
+/// the strings of `arguments`, with conditional arguments expanded when their rules allow them
+pub fn expand_arguments<'a>(arguments: &'a [api::meta::Argument], features: &Features) -> Vec<&'a str> {
+	let mut list = Vec::new();
+	for argument in arguments {
+		match argument {
+			api::meta::Argument::String(value) => list.push(value.as_str()),
+			api::meta::Argument::Object(api::meta::ArgumentRule { rules, value }) => {
+				if !is_allowed(rules, features) {
+					continue;
+				}
+				match value {
+					api::meta::ArgumentValue::String(value) => list.push(value.as_str()),
+					api::meta::ArgumentValue::List(values) => list.extend(values.iter().map(String::as_str)),
+				}
+			}
+		}
+	}
+	list
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn rules(value: serde_json::Value) -> Vec<api::meta::Rule> {
+		serde_json::from_value(value).unwrap()
+	}
+
+	#[test]
+	fn os_rules() {
+		let other = match OS_NAME {
+			api::meta::OsName::Osx => "linux",
+			_ => "osx",
+		};
+		let only_other = rules(serde_json::json!([{ "action": "allow", "os": { "name": other } }]));
+		assert!(!is_allowed(&only_other, &Features::default()));
+		let except_other = rules(serde_json::json!([{ "action": "allow" }, { "action": "disallow", "os": { "name": other } }]));
+		assert!(is_allowed(&except_other, &Features::default()));
+		assert!(is_allowed(&[], &Features::default()));
+	}
+
+	#[test]
+	fn feature_rules() {
+		let arguments: Vec<api::meta::Argument> = serde_json::from_value(serde_json::json!([
+			"--username",
+			{ "rules": [{ "action": "allow", "features": { "is_demo_user": true } }], "value": "--demo" },
+			{
+				"rules": [{ "action": "allow", "features": { "has_custom_resolution": true } }],
+				"value": ["--width", "${resolution_width}"]
+			}
+		]))
+		.unwrap();
+		let features = Features {
+			has_custom_resolution: true,
+			..Default::default()
+		};
+		assert_eq!(expand_arguments(&arguments, &features), ["--username", "--width", "${resolution_width}"]);
+	}
+}
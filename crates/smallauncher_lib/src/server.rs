@@ -0,0 +1,297 @@
+use crate::*;
+
+use std::fmt;
+use std::fs;
+use std::path;
+use std::process;
+use std::str;
+
+const EULA: &str = "#By changing the setting below to TRUE you are indicating your agreement to our EULA (https://aka.ms/MinecraftEULA).
+eula=true
+";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServerOptions {
+	/// initial heap size in MiB (`-Xms`)
+	pub min_memory: Option<u32>,
+	/// maximum heap size in MiB (`-Xmx`)
+	pub max_memory: Option<u32>,
+	/// opens the server window instead of passing `nogui`
+	pub gui: bool,
+	pub jvm_args: Vec<String>,
+}
+
+/// directory of the server installed for `version_name`
+#[inline(always)]
+pub fn server_directory(server_path: &path::Path, version_name: &str) -> path::PathBuf {
+	path!(server_path, version_name)
+}
+
+/// installs `server.jar` of `name` in its own directory under `server_path`, along with the java runtime it needs
+pub fn download_server_version(
+	server_path: &path::Path,
+	jre_path: &path::Path,
+	name: &str,
+	options: &download::DownloadOptions,
+	reporter: &dyn download::Reporter,
+) -> Result<(), error::Error> {
+	let meta = download::get_version_meta(name, &options.network)?;
+	let Some(server) = &meta.downloads.server else {
+		return Err(error::Error::ServerNotAvailable);
+	};
+	let directory = server_directory(server_path, &meta.id);
+	let server_task = download::Task {
+		resource: net::Resource::Meta,
+		url: server.url.clone(),
+		path: path!(&directory, "server.jar"),
+		sha1: server.sha1.clone(),
+		size: server.size,
+		executable: false,
+		lzma: None,
+	};
+	let mut java = download::get_java_runtime(&meta.java_version.component, jre_path, &options.network)?;
+	let phases = vec![
+		(download::Phase::Server, vec![server_task]),
+		(download::Phase::Java, std::mem::take(&mut java.tasks)),
+	];
+
+	fs::create_dir_all(&directory)?;
+	fs::write(path!(&directory, "version.json"), serde_json::to_string_pretty(&meta)?)?;
+	download::run_phases(phases, &java.directories, &java.links, options, reporter)
+}
+
+pub fn eula_accepted(directory: &path::Path) -> bool {
+	let Ok(eula) = fs::read_to_string(path!(directory, "eula.txt")) else {
+		return false;
+	};
+	eula.lines().any(|line| line.trim().eq_ignore_ascii_case("eula=true"))
+}
+
+pub fn accept_eula(directory: &path::Path) -> Result<(), error::Error> {
+	fs::create_dir_all(directory)?;
+	fs::write(path!(directory, "eula.txt"), EULA)?;
+	Ok(())
+}
+
+/// spawns the server installed for `version_name`, the eula has to be accepted first
+pub fn launch_server(
+	server_path: &path::Path,
+	jre_path: &path::Path,
+	version_name: &str,
+	options: &ServerOptions,
+) -> Result<process::Child, error::Error> {
+	let directory = server_directory(server_path, version_name);
+	if !eula_accepted(&directory) {
+		return Err(error::Error::EulaNotAccepted);
+	}
+	let meta: api::meta::Version = file::from_json_file(path!(&directory, "version.json"))?;
+	let mut command = process::Command::new(launch::java_binary(jre_path, &meta.java_version.component));
+	command.current_dir(&directory);
+	if let Some(memory) = options.min_memory {
+		command.arg(format!("-Xms{memory}M"));
+	}
+	if let Some(memory) = options.max_memory {
+		command.arg(format!("-Xmx{memory}M"));
+	}
+	command.args(&options.jvm_args).arg("-jar").arg("server.jar");
+	if !options.gui {
+		command.arg("nogui");
+	}
+
+	log::info!("Spawning command: {:?}", command);
+	Ok(command.spawn()?)
+}
+
+/// `server.properties`, keeping the order, comments and unknown keys of the file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServerProperties {
+	lines: Vec<Line>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Line {
+	Comment(String),
+	Property(String, String),
+}
+
+macro_rules! property {
+	($get:ident, $set:ident, $key:literal, $type:ty) => {
+		#[inline(always)]
+		pub fn $get(&self) -> Option<$type> {
+			self.get_as($key)
+		}
+		#[inline(always)]
+		pub fn $set(&mut self, value: $type) {
+			self.set($key, value)
+		}
+	};
+}
+
+impl ServerProperties {
+	pub fn load(directory: &path::Path) -> Result<Self, error::Error> {
+		match fs::read_to_string(path!(directory, "server.properties")) {
+			Ok(data) => Ok(data.parse().unwrap_or_default()),
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+			Err(e) => Err(e.into()),
+		}
+	}
+	pub fn save(&self, directory: &path::Path) -> Result<(), error::Error> {
+		fs::create_dir_all(directory)?;
+		fs::write(path!(directory, "server.properties"), self.to_string())?;
+		Ok(())
+	}
+	pub fn get(&self, key: &str) -> Option<&str> {
+		self.lines.iter().find_map(|line| match line {
+			Line::Property(k, v) if k == key => Some(v.as_str()),
+			_ => None,
+		})
+	}
+	pub fn get_as<T: str::FromStr>(&self, key: &str) -> Option<T> {
+		self.get(key)?.parse().ok()
+	}
+	pub fn set<T: fmt::Display>(&mut self, key: &str, value: T) {
+		let value = value.to_string();
+		for line in &mut self.lines {
+			if let Line::Property(k, v) = line {
+				if k == key {
+					*v = value;
+					return;
+				}
+			}
+		}
+		self.lines.push(Line::Property(key.to_string(), value));
+	}
+	pub fn remove(&mut self, key: &str) -> Option<String> {
+		let index = self.lines.iter().position(|line| matches!(line, Line::Property(k, _) if k == key))?;
+		match self.lines.remove(index) {
+			Line::Property(_, value) => Some(value),
+			Line::Comment(_) => None,
+		}
+	}
+	pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+		self.lines.iter().filter_map(|line| match line {
+			Line::Property(k, v) => Some((k.as_str(), v.as_str())),
+			Line::Comment(_) => None,
+		})
+	}
+
+	property!(server_port, set_server_port, "server-port", u16);
+	property!(server_ip, set_server_ip, "server-ip", String);
+	property!(motd, set_motd, "motd", String);
+	property!(max_players, set_max_players, "max-players", u32);
+	property!(online_mode, set_online_mode, "online-mode", bool);
+	property!(white_list, set_white_list, "white-list", bool);
+	property!(pvp, set_pvp, "pvp", bool);
+	property!(hardcore, set_hardcore, "hardcore", bool);
+	property!(difficulty, set_difficulty, "difficulty", String);
+	property!(gamemode, set_gamemode, "gamemode", String);
+	property!(level_name, set_level_name, "level-name", String);
+	property!(level_seed, set_level_seed, "level-seed", String);
+	property!(view_distance, set_view_distance, "view-distance", u32);
+	property!(simulation_distance, set_simulation_distance, "simulation-distance", u32);
+}
+
+fn unescape(value: &str) -> String {
+	let mut result = String::with_capacity(value.len());
+	let mut chars = value.chars();
+	while let Some(c) = chars.next() {
+		if c != '\\' {
+			result.push(c);
+			continue;
+		}
+		match chars.next() {
+			Some('n') => result.push('\n'),
+			Some('t') => result.push('\t'),
+			Some('r') => result.push('\r'),
+			Some('u') => {
+				let code: String = chars.by_ref().take(4).collect();
+				match u32::from_str_radix(&code, 16).ok().and_then(char::from_u32) {
+					Some(c) => result.push(c),
+					None => result.push_str(&code),
+				}
+			}
+			Some(c) => result.push(c),
+			None => {}
+		}
+	}
+	result
+}
+
+fn escape(value: &str, key: bool) -> String {
+	let mut result = String::with_capacity(value.len());
+	for c in value.chars() {
+		match c {
+			'\\' => result.push_str("\\\\"),
+			'\n' => result.push_str("\\n"),
+			'\t' => result.push_str("\\t"),
+			'\r' => result.push_str("\\r"),
+			':' | '=' | '#' | '!' => {
+				result.push('\\');
+				result.push(c);
+			}
+			' ' if key => result.push_str("\\ "),
+			c if !c.is_ascii() => result.push_str(&format!("\\u{:04X}", c as u32)),
+			c => result.push(c),
+		}
+	}
+	result
+}
+
+impl str::FromStr for ServerProperties {
+	type Err = std::convert::Infallible;
+
+	fn from_str(data: &str) -> Result<Self, Self::Err> {
+		let lines = data
+			.lines()
+			.map(|line| {
+				let trimmed = line.trim_start();
+				if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with('!') {
+					return Line::Comment(line.to_string());
+				}
+				let mut escaped = false;
+				let split = trimmed.char_indices().find(|&(_, c)| {
+					let found = !escaped && (c == '=' || c == ':');
+					escaped = !escaped && c == '\\';
+					found
+				});
+				match split {
+					Some((i, _)) => Line::Property(unescape(trimmed[..i].trim_end()), unescape(trimmed[i + 1..].trim_start())),
+					None => Line::Property(unescape(trimmed.trim_end()), String::new()),
+				}
+			})
+			.collect();
+		Ok(Self { lines })
+	}
+}
+
+impl fmt::Display for ServerProperties {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		for line in &self.lines {
+			match line {
+				Line::Comment(comment) => writeln!(f, "{comment}")?,
+				Line::Property(key, value) => writeln!(f, "{}={}", escape(key, true), escape(value, false))?,
+			}
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn server_properties_round_trip() {
+		let data = "#Minecraft server properties\nmotd=A Minecraft Server \\u00A7a\\:)\nserver-port=25565\nonline-mode=true\n";
+		let mut properties: ServerProperties = data.parse().unwrap();
+		assert_eq!(properties.motd().as_deref(), Some("A Minecraft Server §a:)"));
+		assert_eq!(properties.server_port(), Some(25565));
+		assert_eq!(properties.online_mode(), Some(true));
+		properties.set_server_port(25566);
+		properties.set_max_players(4);
+		assert_eq!(
+			properties.to_string(),
+			"#Minecraft server properties\nmotd=A Minecraft Server \\u00A7a\\:)\nserver-port=25566\nonline-mode=true\nmax-players=4\n"
+		);
+	}
+}
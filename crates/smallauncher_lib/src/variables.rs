@@ -0,0 +1,87 @@
+use std::collections;
+use std::ffi;
+
+/// values of the `${name}` placeholders in launch arguments
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Variables {
+	map: collections::BTreeMap<String, ffi::OsString>,
+}
+
+impl Variables {
+	pub fn new() -> Self {
+		Self::default()
+	}
+	/// adds `name` or overrides its value
+	pub fn set(&mut self, name: impl Into<String>, value: impl Into<ffi::OsString>) {
+		self.map.insert(name.into(), value.into());
+	}
+	pub fn get(&self, name: &str) -> Option<&ffi::OsStr> {
+		self.map.get(name).map(ffi::OsString::as_os_str)
+	}
+	pub fn remove(&mut self, name: &str) -> Option<ffi::OsString> {
+		self.map.remove(name)
+	}
+	/// adds every variable of `other`, overriding the ones already set
+	pub fn extend(&mut self, other: &Variables) {
+		self.map.extend(other.map.iter().map(|(k, v)| (k.clone(), v.clone())));
+	}
+	pub fn iter(&self) -> impl Iterator<Item = (&str, &ffi::OsStr)> {
+		self.map.iter().map(|(k, v)| (k.as_str(), v.as_os_str()))
+	}
+
+	/// names of the placeholders in `arg` without a value
+	pub fn unknown<'a>(&self, arg: &'a str) -> Vec<&'a str> {
+		placeholders(arg)
+			.filter(|(_, name)| !self.map.contains_key(*name))
+			.map(|(_, name)| name)
+			.collect()
+	}
+
+	/// replaces every known `${name}` in `arg`, unknown placeholders are kept as they are and logged
+	pub fn substitute(&self, arg: &str) -> ffi::OsString {
+		let mut result = ffi::OsString::with_capacity(arg.len());
+		let mut rest = 0;
+		for (start, name) in placeholders(arg) {
+			let Some(value) = self.map.get(name) else {
+				log::warn!("Unknown variable ${{{name}}} in argument {arg:?}");
+				continue;
+			};
+			result.push(&arg[rest..start]);
+			result.push(value);
+			rest = start + name.len() + 3;
+		}
+		result.push(&arg[rest..]);
+		result
+	}
+}
+
+/// start offset and name of every `${name}` in `arg`
+fn placeholders(arg: &str) -> impl Iterator<Item = (usize, &str)> {
+	let mut offset = 0;
+	std::iter::from_fn(move || {
+		let start = offset + arg[offset..].find("${")?;
+		let end = start + arg[start..].find('}')?;
+		offset = end + 1;
+		Some((start, &arg[start + 2..end]))
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn substitute_inside_arguments() {
+		let mut variables = Variables::new();
+		variables.set("natives_directory", "/natives");
+		variables.set("launcher_name", "smallauncher");
+		assert_eq!(
+			variables.substitute("-Djava.library.path=${natives_directory}"),
+			"-Djava.library.path=/natives"
+		);
+		assert_eq!(variables.substitute("${launcher_name}-${natives_directory}/x"), "smallauncher-/natives/x");
+		assert_eq!(variables.substitute("${missing}:${launcher_name}"), "${missing}:smallauncher");
+		assert_eq!(variables.unknown("${missing}:${launcher_name}"), ["missing"]);
+		assert_eq!(variables.substitute("${unterminated"), "${unterminated");
+	}
+}